@@ -0,0 +1,302 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+use rayon::prelude::*;
+
+// base p digits of a nonnegative integer, least significant first
+#[allow(dead_code)]
+pub fn digits(n: i64, p: i64) -> Vec<i64> {
+    let mut len = 0;
+    let mut m = n;
+    while m > 0 {
+        m /= p;
+        len += 1;
+    }
+    expand(&Frac { numer: n, denom: 1 }, p, len).expanded
+}
+
+// number of carries when adding a and b in base p, from position `from` onwards
+fn carries(a: i64, b: i64, p: i64, from: usize) -> i64 {
+    let (da, db) = join(p as usize, || digits(a, p), || digits(b, p));
+    let mut carry = 0;
+    let mut count = 0;
+    for i in 0..da.len().max(db.len()) {
+        carry = (da.get(i).unwrap_or(&0) + db.get(i).unwrap_or(&0) + carry) / p;
+        if carry == 1 && i >= from {
+            count += 1;
+        }
+    }
+    count
+}
+
+// Legendre: v_p(n!) = (n - s_p(n)) / (p - 1) with s_p the base p digit sum
+#[allow(dead_code)]
+pub fn valuation_of_factorial(n: i64, p: i64) -> i64 {
+    (n - digits(n, p).iter().sum::<i64>()) / (p - 1)
+}
+
+// Kummer: v_p(C(n, k)) is the number of carries when adding k and n - k in base p
+#[allow(dead_code)]
+pub fn valuation_of_binomial(n: i64, k: i64, p: i64) -> i64 {
+    if k < 0 || k > n {
+        return 0;
+    }
+    carries(k, n - k, p, 0)
+}
+
+fn small_binomial(n: i64, k: i64, p: i64) -> i64 {
+    if k > n {
+        return 0;
+    }
+    let (num, den) = (0..k).fold((1, 1), |(num, den), i| {
+        ((num * ((n - i) % p)) % p, (den * ((i + 1) % p)) % p)
+    });
+    num * mod_inverse(den, p).unwrap() % p
+}
+
+// Lucas: C(n, k) is the product of the binomials of the base p digits mod p
+#[allow(dead_code)]
+pub fn binomial_mod_p(n: i64, k: i64, p: i64) -> i64 {
+    if k < 0 || k > n {
+        return 0;
+    }
+    let (dn, dk) = join(p as usize, || digits(n, p), || digits(k, p));
+    dn.iter()
+        .enumerate()
+        .map(|(i, &ni)| small_binomial(ni, *dk.get(i).unwrap_or(&0), p))
+        .fold(1, |acc, b| acc * b % p)
+}
+
+// polynomials in z, as coefficients mod p^e of z^0 .. z^(e - 1), stand for products of
+// integers z + i with z a multiple of p, where z^e vanishes mod p^e
+fn poly_mul(a: &[i64], b: &[i64], m: i64) -> Vec<i64> {
+    let mut c = vec![0i128; a.len()];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate().take(a.len() - i) {
+            c[i + j] = (c[i + j] + x as i128 * y as i128) % m as i128;
+        }
+    }
+    c.into_iter().map(|x| x as i64).collect()
+}
+
+// q(z + c) by Horner's rule, for c a multiple of p
+fn poly_shift(q: &[i64], c: i64, m: i64) -> Vec<i64> {
+    let mut shifted = vec![0; q.len()];
+    for &a in q.iter().rev() {
+        shifted = (0..q.len())
+            .map(|k| {
+                let below = if k > 0 { shifted[k - 1] as i128 } else { 0 };
+                ((shifted[k] as i128 * c as i128 + below) % m as i128) as i64
+            })
+            .collect();
+        shifted[0] = (shifted[0] + a) % m;
+    }
+    shifted
+}
+
+fn poly_eval(q: &[i64], z: i64, m: i64) -> i64 {
+    q.iter().rev().fold(0, |acc, &a| {
+        ((acc as i128 * z as i128 + a as i128) % m as i128) as i64
+    })
+}
+
+// the units in 1..=x mod p^e in blocks: P_j(z) is the product of the units in z .. z + p^(j+1)
+// as a polynomial in z, P_0(z) = (z + 1) ... (z + p - 1) and P_(j+1)(z) is the product of the
+// P_j(z + i p^(j+1)) for i < p, so x takes one evaluation per base p digit instead of x
+// multiplications; about p e^2 log_p(x) operations in all
+fn factorial_unit_blocks(x: i64, p: i64, e: usize, m: i64) -> i64 {
+    let linear = |c: i64| {
+        let mut q = vec![0; e];
+        q[0] = c % m;
+        if e > 1 {
+            q[1] = 1;
+        }
+        q
+    };
+    let (blocks, rest) = ((x + 1) / p, (x + 1) % p);
+    let mut one = vec![0; e];
+    one[0] = 1 % m;
+    let mut levels = vec![(1..p).fold(one, |q, i| poly_mul(&q, &linear(i), m))];
+    let mut size = p;
+    while size <= blocks {
+        let last = levels.last().unwrap();
+        let shift = (size as i128 % m as i128) as i64;
+        let next = (1..p).fold(last.clone(), |q, i| {
+            let c = (shift as i128 * i as i128 % m as i128) as i64;
+            poly_mul(&q, &poly_shift(last, c, m), m)
+        });
+        levels.push(next);
+        size *= p;
+    }
+
+    let mut unit = 1 % m;
+    let mut start = 0i64;
+    let mut remaining = blocks;
+    for (j, level) in levels.iter().enumerate().rev() {
+        let count = p.pow(j as u32);
+        while remaining >= count {
+            let value = poly_eval(level, start % m, m);
+            unit = (unit as i128 * value as i128 % m as i128) as i64;
+            start += count * p;
+            remaining -= count;
+        }
+    }
+    (1..rest).fold(unit, |acc, i| {
+        (acc as i128 * ((start + i) % m) as i128 % m as i128) as i64
+    })
+}
+
+// product of the integers in 1..=x not divisible by p, mod m; when m = p^e and x is large
+// enough for it to pay off this goes through factorial_unit_blocks
+pub fn factorial_unit(x: i64, p: i64, m: i64) -> i64 {
+    let mut e = 0;
+    let mut power = 1i64;
+    while power < m {
+        power = power.saturating_mul(p);
+        e += 1;
+    }
+    if power == m && m > 1 && x > p * (e * e * e) as i64 {
+        return factorial_unit_blocks(x, p, e, m);
+    }
+    let mul = |a: i64, b: i64| (a as i128 * b as i128 % m as i128) as i64;
+    if parallel(x.max(0) as usize) {
+        (1..=x)
+            .into_par_iter()
+            .filter(|i| i % p != 0)
+            .map(|i| i % m)
+            .reduce(|| 1 % m, mul)
+    } else {
+        (1..=x)
+            .filter(|i| i % p != 0)
+            .fold(1 % m, |a, i| mul(a, i % m))
+    }
+}
+
+// Granville: C(n, k) mod p^e from the p-free factorials of the residues of n / p^j,
+// k / p^j and (n - k) / p^j mod p^e
+#[allow(dead_code)]
+pub fn binomial_mod_prime_power(n: i64, k: i64, p: i64, e: u32) -> i64 {
+    if k < 0 || k > n {
+        return 0;
+    }
+    let m = p.pow(e);
+    let r = n - k;
+    let e0 = carries(k, r, p, 0);
+    if e0 >= e as i64 {
+        return 0;
+    }
+    let mut unit = 1i128;
+    let mut scale = 1;
+    while n / scale > 0 {
+        let f = |x: i64| factorial_unit((x / scale) % m, p, m);
+        let inv = mod_inverse((f(k) as i128 * f(r) as i128 % m as i128) as i64, m).unwrap();
+        unit = unit * f(n) as i128 % m as i128 * inv as i128 % m as i128;
+        scale *= p;
+    }
+    let sign = if p == 2 && e >= 3 {
+        1
+    } else if carries(k, r, p, e as usize - 1) % 2 == 1 {
+        -1
+    } else {
+        1
+    };
+    (sign * unit * p.pow(e0 as u32) as i128).rem_euclid(m as i128) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binomial(n: i64, k: i64) -> i128 {
+        (0..k).fold(1i128, |acc, i| acc * (n - i) as i128 / (i + 1) as i128)
+    }
+
+    #[test]
+    fn testing_legendre() {
+        assert_eq!(digits(100, 3), vec![1, 0, 2, 0, 1]);
+        assert_eq!(valuation_of_factorial(100, 5), 24);
+        assert_eq!(valuation_of_factorial(100, 2), 97);
+        assert_eq!(valuation_of_factorial(0, 7), 0);
+    }
+
+    #[test]
+    fn testing_kummer_and_lucas() {
+        for p in [2, 3, 5, 7] {
+            for n in 0..40 {
+                for k in 0..=n {
+                    let c = binomial(n, k);
+                    let mut v = 0;
+                    let mut x = c;
+                    while x % p as i128 == 0 {
+                        x /= p as i128;
+                        v += 1;
+                    }
+                    assert_eq!(valuation_of_binomial(n, k, p), v);
+                    assert_eq!(binomial_mod_p(n, k, p) as i128, c % p as i128);
+                }
+            }
+        }
+        // 1000 = (5, 11, 12) and 300 = (1, 10, 1) in base 13
+        assert_eq!(binomial_mod_p(1000, 300, 13), 12 * 11 * 5 % 13);
+    }
+
+    #[test]
+    fn testing_factorial_unit() {
+        let direct = |x: i64, p: i64, m: i64| {
+            (1..=x)
+                .filter(|i| i % p != 0)
+                .fold(1 % m, |acc, i| (acc as i128 * i as i128 % m as i128) as i64)
+        };
+        for (p, e) in [(2i64, 1usize), (2, 5), (3, 3), (5, 2), (7, 4), (13, 2)] {
+            let m = p.pow(e as u32);
+            for x in (0..3000)
+                .step_by(7)
+                .chain([p.pow(5) - 1, p.pow(5), 4 * p.pow(4) + 3])
+            {
+                assert_eq!(factorial_unit_blocks(x, p, e, m), direct(x, p, m));
+                assert_eq!(factorial_unit(x, p, m), direct(x, p, m));
+            }
+        }
+        // the units below p^e multiply to -1
+        let m = 7i64.pow(20);
+        assert_eq!(factorial_unit(m - 1, 7, m), m - 1);
+    }
+
+    #[test]
+    fn testing_granville() {
+        for (p, e) in [
+            (2i64, 1u32),
+            (2, 2),
+            (2, 3),
+            (2, 5),
+            (3, 2),
+            (3, 3),
+            (5, 2),
+            (7, 2),
+        ] {
+            let m = p.pow(e) as i128;
+            for n in 0..50 {
+                for k in 0..=n {
+                    assert_eq!(
+                        binomial_mod_prime_power(n, k, p, e) as i128,
+                        binomial(n, k) % m,
+                        "C({}, {}) mod {}^{}",
+                        n,
+                        k,
+                        p,
+                        e
+                    );
+                }
+            }
+        }
+
+        // moduli past 2^32, where the products of residues leave i64, agree with smaller powers
+        let (n, k) = (1_000_000_000_000, 123_456_789);
+        let c = binomial_mod_prime_power(n, k, 7, 12);
+        assert!((0..7i64.pow(12)).contains(&c));
+        for e in 1..12 {
+            assert_eq!(c % 7i64.pow(e), binomial_mod_prime_power(n, k, 7, e));
+        }
+        assert_eq!(c % 7, binomial_mod_p(n, k, 7));
+    }
+}
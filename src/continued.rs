@@ -0,0 +1,591 @@
+use crate::frac::*;
+use crate::padic::*;
+
+// Ruban's floor takes the digits of x at the places <= 0 from 0..p, Browkin's takes them
+// from -(p-1)/2..=(p-1)/2 (p odd), so that every rational has a finite Browkin expansion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Ruban,
+    Browkin,
+}
+
+// partial quotients b_0, b_1, ... of x = b_0 + 1/(b_1 + 1/(b_2 + ...))
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expansion {
+    Finite(Vec<Frac>),
+    Periodic {
+        preperiod: Vec<Frac>,
+        period: Vec<Frac>,
+    },
+    // the quotients found before max_terms, an i64 overflow or the known digits ran out
+    Truncated(Vec<Frac>),
+}
+
+// the residue r / p^k with r chosen from 0..p^(k+1) or centered
+fn floor_from_residue(r: i128, k: i64, p: i64, algorithm: Algorithm) -> Option<Frac> {
+    let m = (p as i128).checked_pow(k as u32 + 1)?;
+    let mut r = r.rem_euclid(m);
+    if algorithm == Algorithm::Browkin && 2 * r > m {
+        r -= m;
+    }
+    Some(Frac::reduce(r.try_into().ok()?, p.checked_pow(k as u32)?))
+}
+
+// s(x) for a rational x, None on overflow
+fn floor_frac(x: &Frac, p: i64, algorithm: Algorithm) -> Option<Frac> {
+    let Some(v) = x.valuation(p) else {
+        return Some(Frac { numer: 0, denom: 1 });
+    };
+    if v >= 1 {
+        return Some(Frac { numer: 0, denom: 1 });
+    }
+    let k = -v;
+    // x p^k = a / b with b prime to p
+    let a = x.numer as i128;
+    let b = x.denom as i128 / (p as i128).pow(k as u32);
+    let m = (p as i128).checked_pow(k as u32 + 1)?;
+    let m64: i64 = m.try_into().ok()?;
+    let inv = mod_inverse(b.rem_euclid(m) as i64, m64)? as i128;
+    floor_from_residue(a.rem_euclid(m) * inv % m, k, p, algorithm)
+}
+
+// s(x) from the known digits of x, None when they do not reach the place 0
+fn floor_padic(x: &Padic, algorithm: Algorithm) -> Option<Frac> {
+    let x = x.normalized();
+    if x.offset >= 1 || x.expanded.is_empty() {
+        return if x.precision() >= 1 {
+            Some(Frac { numer: 0, denom: 1 })
+        } else {
+            None
+        };
+    }
+    if x.precision() < 1 {
+        return None;
+    }
+    let k = -x.offset;
+    let r = x.expanded[..=k as usize]
+        .iter()
+        .rev()
+        .try_fold(0i128, |acc, &d| {
+            acc.checked_mul(x.p as i128)?.checked_add(d as i128)
+        })?;
+    floor_from_residue(r, k, x.p, algorithm)
+}
+
+fn check(p: i64, algorithm: Algorithm) {
+    assert!(
+        p != 2 || algorithm == Algorithm::Ruban,
+        "Browkin's continued fractions need an odd prime"
+    );
+}
+
+// stops at a zero remainder (finite), a complete quotient `same` as an earlier one (periodic)
+// or max_terms; next gives None on failure and Some(None) at a zero remainder
+fn run<S, F, N, E>(x: S, max_terms: usize, floor: F, next: N, same: E) -> Expansion
+where
+    F: Fn(&S) -> Option<Frac>,
+    N: Fn(&S, &Frac) -> Option<Option<S>>,
+    E: Fn(&S, &S) -> bool,
+{
+    let mut states = vec![x];
+    let mut quotients = Vec::new();
+    while quotients.len() < max_terms {
+        let x = states.last().unwrap();
+        let Some(b) = floor(x) else {
+            return Expansion::Truncated(quotients);
+        };
+        quotients.push(b);
+        match next(x, &b) {
+            None => return Expansion::Truncated(quotients),
+            Some(None) => return Expansion::Finite(quotients),
+            Some(Some(y)) => {
+                if let Some(i) = states.iter().position(|s| same(s, &y)) {
+                    let period = quotients.split_off(i);
+                    return Expansion::Periodic {
+                        preperiod: quotients,
+                        period,
+                    };
+                }
+                states.push(y);
+            }
+        }
+    }
+    Expansion::Truncated(quotients)
+}
+
+#[allow(dead_code)]
+pub fn continued_fraction(x: &Frac, p: i64, algorithm: Algorithm, max_terms: usize) -> Expansion {
+    check(p, algorithm);
+    run(
+        Frac::reduce(x.numer, x.denom),
+        max_terms,
+        |x| floor_frac(x, p, algorithm),
+        |x, b| {
+            let rest = x.checked_add(&b.neg())?;
+            if rest.numer == 0 {
+                return Some(None);
+            }
+            Some(Some(Frac::reduce(rest.denom, rest.numer)))
+        },
+        |x, y| x == y,
+    )
+}
+
+// x = (P + √d) / Q with the square root of d in Q_p whose unit part is the lift of the smallest
+// residue, None when d is not a nonzero square in Q_p or p = 2. The complete quotients keep
+// that form, 1 / ((P' + √d) / Q) = (-P' + √d) / ((d - P'^2) / Q), so periodicity is exact.
+#[allow(dead_code)]
+pub fn continued_fraction_quadratic(
+    numer: &Frac,
+    d: i64,
+    denom: &Frac,
+    p: i64,
+    algorithm: Algorithm,
+    max_terms: usize,
+) -> Option<Expansion> {
+    check(p, algorithm);
+    if p == 2 || d == 0 {
+        return None;
+    }
+    let mut k = 0;
+    let mut u = d;
+    while u % p == 0 {
+        u /= p;
+        k += 1;
+    }
+    if k % 2 == 1 || mod_pow(u.rem_euclid(p), (p - 1) / 2, p) != 1 {
+        return None;
+    }
+    let work = 40;
+    // the smallest residue t with t^2 = u mod p, lifted by Newton's r - (r^2 - u) / 2r, which
+    // doubles the digits that are right each step
+    let t = (1..p).find(|t| t * t % p == u.rem_euclid(p))?;
+    let int = |n: i64| expand(&Frac { numer: n, denom: 1 }, p, work);
+    let mut unit_root = int(t);
+    loop {
+        let error = unit_root.mul(&unit_root).sub(&int(u));
+        if error.valuation().is_none_or(|v| v >= work as i64) {
+            break;
+        }
+        unit_root = unit_root.sub(&error.div(&int(2).mul(&unit_root))?);
+    }
+    let root = Padic::with_digits(
+        p,
+        unit_root.expanded.clone(),
+        unit_root.offset + k / 2,
+        None,
+    );
+    // when d is a rational square the root is s or -s, whichever is t p^(k/2) mod p^(k/2 + 1)
+    let s = if d > 0 { d.isqrt() } else { 0 };
+    let rational = (s * s == d).then(|| {
+        let sign = if (s / p.pow(k as u32 / 2)) % p == t {
+            1
+        } else {
+            -1
+        };
+        Frac {
+            numer: sign * s,
+            denom: 1,
+        }
+    });
+    let to_padic = |(a, q): &(Frac, Frac)| expand(a, p, work).add(&root).div(&expand(q, p, work));
+    // (P + √d) / Q as a rational, for comparing states by value
+    let value = |(a, q): &(Frac, Frac), r: &Frac| a.checked_add(r)?.checked_div(q);
+    Some(run(
+        (
+            Frac::reduce(numer.numer, numer.denom),
+            Frac::reduce(denom.numer, denom.denom),
+        ),
+        max_terms,
+        |x| floor_padic(&to_padic(x)?, algorithm),
+        |(a, q), b| {
+            let a = a.checked_add(&b.checked_mul(q)?.neg())?;
+            let d = Frac { numer: d, denom: 1 };
+            let rest = d.checked_add(&a.checked_mul(&a)?.neg())?;
+            if rest.numer == 0 {
+                // P' = ±√d, so the remainder (P' + √d) / Q is zero or the rational Q / 2P',
+                // whose inverse is (Q / 2P' - √d + √d) / 1
+                if rational.is_some_and(|r| r == a.neg()) {
+                    return Some(None);
+                }
+                let inverse = q.checked_div(&a.checked_mul(&Frac { numer: 2, denom: 1 })?)?;
+                let a = inverse.checked_add(&a.neg())?;
+                return Some(Some((
+                    Frac::reduce(a.numer, a.denom),
+                    Frac { numer: 1, denom: 1 },
+                )));
+            }
+            let q = rest.checked_div(q)?;
+            Some(Some((a.neg(), Frac::reduce(q.numer, q.denom))))
+        },
+        // the pairs (P, Q) are unique unless √d is rational
+        |x, y| match &rational {
+            Some(r) => value(x, r).is_some_and(|v| Some(v) == value(y, r)),
+            None => x == y,
+        },
+    ))
+}
+
+// the quotients that the known digits of x determine
+#[allow(dead_code)]
+pub fn continued_fraction_padic(x: &Padic, algorithm: Algorithm, max_terms: usize) -> Vec<Frac> {
+    check(x.p, algorithm);
+    let expansion = run(
+        x.clone(),
+        max_terms,
+        |x| floor_padic(x, algorithm),
+        |x, b| {
+            let rest = x.sub(&expand(b, x.p, x.expanded.len()));
+            // a remainder that is zero to the known precision ends the known quotients
+            rest.valuation()?;
+            Some(Some(rest.inverse()?))
+        },
+        // complete quotients known to finite precision are never identified
+        |_, _| false,
+    );
+    match expansion {
+        Expansion::Finite(q) | Expansion::Truncated(q) => q,
+        Expansion::Periodic {
+            mut preperiod,
+            period,
+        } => {
+            preperiod.extend(period);
+            preperiod
+        }
+    }
+}
+
+// A_n / B_n with A_n = b_n A_(n-1) + A_(n-2) and B_n = b_n B_(n-1) + B_(n-2), as far as they
+// fit in i64
+#[allow(dead_code)]
+pub fn convergents(quotients: &[Frac]) -> Vec<Frac> {
+    let mut result = Vec::new();
+    let (mut a0, mut a1) = (Frac { numer: 0, denom: 1 }, Frac { numer: 1, denom: 1 });
+    let (mut b0, mut b1) = (Frac { numer: 1, denom: 1 }, Frac { numer: 0, denom: 1 });
+    for q in quotients {
+        let next = |x1: &Frac, x0: &Frac| q.checked_mul(x1)?.checked_add(x0);
+        let (Some(a), Some(b)) = (next(&a1, &a0), next(&b1, &b0)) else {
+            break;
+        };
+        let Some(c) = a.checked_div(&b) else {
+            break;
+        };
+        result.push(Frac::reduce(c.numer, c.denom));
+        (a0, a1, b0, b1) = (a1, a, b1, b);
+    }
+    result
+}
+
+// x = a_0 + 1/(a_1 + 1/(a_2 + ...)) over the reals by Euclid's algorithm, a_i >= 1 for i >= 1
+// and the last quotient > 1 unless x is an integer
+#[allow(dead_code)]
+pub fn real_continued_fraction(x: &Frac) -> Vec<i64> {
+    let x = Frac::reduce(x.numer, x.denom);
+    let (mut n, mut d) = (x.numer, x.denom);
+    let mut quotients = Vec::new();
+    while d != 0 {
+        let a = n.div_euclid(d);
+        quotients.push(a);
+        (n, d) = (d, n.rem_euclid(d));
+    }
+    quotients
+}
+
+// (p_n, q_n) from p_n = a_n p_(n-1) + p_(n-2), starting from p_(-1) / q_(-1) = 1 / 0 and
+// p_(-2) / q_(-2) = 0 / 1, which never exceed the numerator and denominator of x
+fn real_pairs(quotients: &[i64]) -> Vec<(i64, i64)> {
+    let mut pairs = vec![(0, 1), (1, 0)];
+    for &a in quotients {
+        let (p1, q1) = pairs[pairs.len() - 1];
+        let (p0, q0) = pairs[pairs.len() - 2];
+        pairs.push((a * p1 + p0, a * q1 + q0));
+    }
+    pairs
+}
+
+#[allow(dead_code)]
+pub fn real_convergents(x: &Frac) -> Vec<Frac> {
+    real_pairs(&real_continued_fraction(x))[2..]
+        .iter()
+        .map(|&(p, q)| Frac { numer: p, denom: q })
+        .collect()
+}
+
+// the intermediate fractions (p_(n-2) + k p_(n-1)) / (q_(n-2) + k q_(n-1)) for 0 < k < a_n and
+// n >= 1, lying between the convergents p_(n-2) / q_(n-2) and p_n / q_n
+#[allow(dead_code)]
+pub fn semiconvergents(x: &Frac) -> Vec<Frac> {
+    let quotients = real_continued_fraction(x);
+    let pairs = real_pairs(&quotients);
+    (1..quotients.len())
+        .flat_map(|n| {
+            let (p0, q0) = pairs[n];
+            let (p1, q1) = pairs[n + 1];
+            (1..quotients[n]).map(move |k| Frac {
+                numer: p0 + k * p1,
+                denom: q0 + k * q1,
+            })
+        })
+        .collect()
+}
+
+// |x - a / b| compared through x.numer * b - a * x.denom over the common x.denom
+fn closer(x: &Frac, a: &Frac, b: &Frac) -> bool {
+    let error =
+        |y: &Frac| (x.numer as i128 * y.denom as i128 - y.numer as i128 * x.denom as i128).abs();
+    error(a) * (b.denom as i128) < error(b) * (a.denom as i128)
+        || error(a) * (b.denom as i128) == error(b) * (a.denom as i128) && a.denom <= b.denom
+}
+
+// the closest fraction to x with denominator at most max_denom: the last convergent within
+// the bound or the largest semiconvergent after it, whichever is closer
+#[allow(dead_code)]
+pub fn best_approximation(x: &Frac, max_denom: i64) -> Frac {
+    assert!(max_denom >= 1, "denominator bound below 1");
+    let x = Frac::reduce(x.numer, x.denom);
+    if x.denom <= max_denom {
+        return x;
+    }
+    let pairs = real_pairs(&real_continued_fraction(&x));
+    let n = (2..pairs.len())
+        .take_while(|&i| pairs[i].1 <= max_denom)
+        .last()
+        .unwrap();
+    let (p1, q1) = pairs[n];
+    let (p0, q0) = pairs[n - 1];
+    let k = (max_denom - q0) / q1;
+    let convergent = Frac {
+        numer: p1,
+        denom: q1,
+    };
+    let semi = Frac {
+        numer: p0 + k * p1,
+        denom: q0 + k * q1,
+    };
+    if k > 0 && closer(&x, &semi, &convergent) {
+        semi
+    } else {
+        convergent
+    }
+}
+
+// the path from 1 down the Stern-Brocot tree, R^a_0 L^a_1 R^a_2 ... with the last exponent
+// lowered by one; None unless x > 0
+#[allow(dead_code)]
+pub fn stern_brocot_path(x: &Frac) -> Option<String> {
+    let x = Frac::reduce(x.numer, x.denom);
+    if x.numer <= 0 {
+        return None;
+    }
+    let quotients = real_continued_fraction(&x);
+    let last = quotients.len() - 1;
+    Some(
+        quotients
+            .iter()
+            .enumerate()
+            .map(|(i, &a)| {
+                let step = if i % 2 == 0 { "R" } else { "L" };
+                step.repeat((a - (i == last) as i64) as usize)
+            })
+            .collect(),
+    )
+}
+
+// follows the mediants between 0/1 and 1/0, None on a character other than L and R or
+// when the mediants outgrow i64
+#[allow(dead_code)]
+pub fn from_stern_brocot_path(path: &str) -> Option<Frac> {
+    let (mut left, mut right) = ((0i64, 1i64), (1i64, 0i64));
+    let mut mediant = (1, 1);
+    for c in path.chars() {
+        match c {
+            'L' => right = mediant,
+            'R' => left = mediant,
+            _ => return None,
+        }
+        mediant = (left.0.checked_add(right.0)?, left.1.checked_add(right.1)?);
+    }
+    Some(Frac {
+        numer: mediant.0,
+        denom: mediant.1,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frac(numer: i64, denom: i64) -> Frac {
+        Frac { numer, denom }
+    }
+
+    #[test]
+    fn testing_rational_expansions() {
+        // -1 = 4 + 1 / (-1/5) and -1/5 = 24/5 + 1 / (-1/5) in Ruban's expansion over Q_5
+        assert_eq!(
+            continued_fraction(&frac(-1, 1), 5, Algorithm::Ruban, 20),
+            Expansion::Periodic {
+                preperiod: vec![frac(4, 1)],
+                period: vec![frac(24, 5)],
+            }
+        );
+        // Browkin's expansions of rationals terminate and their last convergent is exact
+        for x in [frac(-1, 1), frac(7, 30), frac(-123, 49), frac(1000, 3)] {
+            let Expansion::Finite(q) = continued_fraction(&x, 7, Algorithm::Browkin, 50) else {
+                panic!("Browkin expansion of {} does not terminate", x);
+            };
+            assert_eq!(*convergents(&q).last().unwrap(), x);
+            assert!(q[1..].iter().all(|b| b.valuation(7).is_some_and(|v| v < 0)));
+        }
+        // Ruban's expansion of a rational is finite or ends in the period p - 1/p
+        assert_eq!(
+            continued_fraction(&frac(3, 2), 5, Algorithm::Ruban, 50),
+            Expansion::Periodic {
+                preperiod: vec![frac(4, 1), frac(23, 5)],
+                period: vec![frac(24, 5)],
+            }
+        );
+        let Expansion::Finite(q) = continued_fraction(&frac(7, 1), 5, Algorithm::Ruban, 50) else {
+            panic!("Ruban expansion of 7 does not terminate");
+        };
+        assert_eq!(q, vec![frac(2, 1), frac(1, 5)]);
+        assert_eq!(*convergents(&q).last().unwrap(), frac(7, 1));
+    }
+
+    #[test]
+    fn testing_quadratic_expansions() {
+        // x = (1 + √101) / 10 solves x = 1/5 + 1/x in Q_5
+        for algorithm in [Algorithm::Ruban, Algorithm::Browkin] {
+            assert_eq!(
+                continued_fraction_quadratic(&frac(1, 1), 101, &frac(10, 1), 5, algorithm, 20),
+                Some(Expansion::Periodic {
+                    preperiod: vec![],
+                    period: vec![frac(1, 5)],
+                })
+            );
+        }
+        assert_eq!(
+            continued_fraction_quadratic(&frac(0, 1), 2, &frac(1, 1), 5, Algorithm::Ruban, 20),
+            None
+        );
+
+        // 14 = 2^2 mod 5 has two roots, the one chosen being 2 mod 5
+        let Some(Expansion::Truncated(q)) =
+            continued_fraction_quadratic(&frac(0, 1), 14, &frac(1, 1), 5, Algorithm::Ruban, 10)
+        else {
+            panic!("Ruban expansion of √14 stops early");
+        };
+        assert_eq!(q.len(), 10);
+        let last = expand(convergents(&q).last().unwrap(), 5, 20);
+        assert_eq!(last.expanded[0], 2);
+        let error = last.mul(&last).sub(&expand(&frac(14, 1), 5, 20));
+        assert!(error.valuation().is_none_or(|v| v >= 4));
+
+        // rational roots: √16 = -4 in Q_5 and √4 = -2 in Q_3, as they are 1 mod p
+        assert_eq!(
+            continued_fraction_quadratic(&frac(0, 1), 16, &frac(1, 1), 5, Algorithm::Browkin, 20),
+            Some(Expansion::Finite(vec![frac(1, 1), frac(-1, 5)]))
+        );
+        assert_eq!(
+            continued_fraction_quadratic(&frac(1, 1), 4, &frac(1, 1), 3, Algorithm::Browkin, 20),
+            Some(Expansion::Finite(vec![frac(-1, 1)]))
+        );
+        assert_eq!(
+            continued_fraction_quadratic(&frac(0, 1), 16, &frac(1, 1), 5, Algorithm::Ruban, 20),
+            Some(Expansion::Periodic {
+                preperiod: vec![frac(1, 1)],
+                period: vec![frac(24, 5)],
+            })
+        );
+
+        // the convergents of √6 approach it in Q_5
+        let expansion =
+            continued_fraction_quadratic(&frac(0, 1), 6, &frac(1, 1), 5, Algorithm::Browkin, 12)
+                .unwrap();
+        let q = match expansion {
+            Expansion::Finite(q) | Expansion::Truncated(q) => q,
+            Expansion::Periodic {
+                mut preperiod,
+                period,
+            } => {
+                preperiod.extend(period.iter().cycle().take(12));
+                preperiod
+            }
+        };
+        let c = convergents(&q);
+        let last = expand(c.last().unwrap(), 5, 20);
+        let error = last.mul(&last).sub(&expand(&frac(6, 1), 5, 20));
+        assert!(error.valuation().is_none_or(|v| v >= 4));
+    }
+
+    #[test]
+    fn testing_padic_expansion() {
+        // the quotients of a rational agree whether read from its digits or computed exactly
+        let x = frac(-5, 11);
+        let Expansion::Finite(exact) = continued_fraction(&x, 7, Algorithm::Browkin, 50) else {
+            panic!("Browkin expansion of -5/11 does not terminate");
+        };
+        assert_eq!(exact, vec![frac(-3, 1), frac(15, 7), frac(-4, 7)]);
+        let from_digits = continued_fraction_padic(&expand(&x, 7, 30), Algorithm::Browkin, 50);
+        assert_eq!(from_digits[..3], exact[..]);
+    }
+
+    #[test]
+    fn testing_real_expansions() {
+        let x = frac(415, 93);
+        assert_eq!(real_continued_fraction(&x), vec![4, 2, 6, 7]);
+        assert_eq!(real_continued_fraction(&frac(-7, 3)), vec![-3, 1, 2]);
+        assert_eq!(
+            real_convergents(&x),
+            vec![frac(4, 1), frac(9, 2), frac(58, 13), frac(415, 93)]
+        );
+        // the real convergents agree with the general recurrence
+        let quotients: Vec<Frac> = real_continued_fraction(&x)
+            .iter()
+            .map(|&a| frac(a, 1))
+            .collect();
+        assert_eq!(convergents(&quotients), real_convergents(&x));
+
+        // 4 + 1/(2 + 1/k) for k < 6 and 9/2 + ... for k < 7
+        let semi = semiconvergents(&x);
+        assert_eq!(semi[0], frac(5, 1));
+        assert_eq!(semi[1], frac(13, 3));
+        assert_eq!(semi.len(), 1 + 5 + 6);
+        assert!(semi.contains(&frac(67, 15)));
+    }
+
+    #[test]
+    fn testing_best_approximation() {
+        let pi = frac(3141592653589793, 1000000000000000);
+        assert_eq!(best_approximation(&pi, 10), frac(22, 7));
+        assert_eq!(best_approximation(&pi, 100), frac(311, 99));
+        assert_eq!(best_approximation(&pi, 1000), frac(355, 113));
+        assert_eq!(best_approximation(&frac(1, 3), 2), frac(1, 2));
+        assert_eq!(best_approximation(&frac(3, 7), 100), frac(3, 7));
+
+        // brute force against every denominator up to 30
+        let x = frac(1234, 4321);
+        for bound in 1..30 {
+            let best = best_approximation(&x, bound);
+            for d in 1..=bound {
+                let n = (x.numer * d + x.denom / 2).div_euclid(x.denom);
+                let candidate = frac(n, d);
+                assert!(!closer(&x, &candidate, &best) || candidate == best);
+            }
+        }
+    }
+
+    #[test]
+    fn testing_stern_brocot() {
+        assert_eq!(stern_brocot_path(&frac(1, 1)), Some(String::new()));
+        assert_eq!(stern_brocot_path(&frac(3, 2)), Some("RL".to_string()));
+        assert_eq!(stern_brocot_path(&frac(5, 7)), Some("LRRL".to_string()));
+        assert_eq!(from_stern_brocot_path("LRRL"), Some(frac(5, 7)));
+        assert_eq!(from_stern_brocot_path("LRX"), None);
+        assert_eq!(stern_brocot_path(&frac(-1, 2)), None);
+        for x in [frac(415, 93), frac(1, 9), frac(13, 8)] {
+            let path = stern_brocot_path(&x).unwrap();
+            assert_eq!(from_stern_brocot_path(&path), Some(x));
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use rayon::prelude::*;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// Operations estimate their work in elementary steps (a digit operation, a node of a game tree,
+// a candidate digit tried) and only hand it to rayon once the estimate reaches the threshold;
+// below it spawning tasks costs more than the work itself.
+pub const DEFAULT_THRESHOLD: usize = 1 << 14;
+
+static THRESHOLD: AtomicUsize = AtomicUsize::new(DEFAULT_THRESHOLD);
+
+// global for the whole process: 0 parallelizes everything, usize::MAX nothing
+#[allow(dead_code)]
+pub fn set_parallel_threshold(work: usize) {
+    THRESHOLD.store(work, Ordering::Relaxed);
+}
+
+#[allow(dead_code)]
+pub fn parallel_threshold() -> usize {
+    THRESHOLD.load(Ordering::Relaxed)
+}
+
+#[allow(dead_code)]
+pub fn parallel(work: usize) -> bool {
+    work >= parallel_threshold()
+}
+
+#[allow(dead_code)]
+pub fn join<A, B, RA, RB>(work: usize, a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    if parallel(work) {
+        rayon::join(a, b)
+    } else {
+        (a(), b())
+    }
+}
+
+#[allow(dead_code)]
+pub fn map<T, R, F>(items: &[T], work: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    if parallel(work) {
+        items.par_iter().map(f).collect()
+    } else {
+        items.iter().map(f).collect()
+    }
+}
+
+#[allow(dead_code)]
+pub fn map_range<R, F>(range: Range<i64>, work: usize, f: F) -> Vec<R>
+where
+    R: Send,
+    F: Fn(i64) -> R + Sync + Send,
+{
+    if parallel(work) {
+        range.into_par_iter().map(f).collect()
+    } else {
+        range.map(f).collect()
+    }
+}
+
+#[allow(dead_code)]
+pub fn all<T, F>(items: &[T], work: usize, f: F) -> bool
+where
+    T: Sync,
+    F: Fn(&T) -> bool + Sync + Send,
+{
+    if parallel(work) {
+        items.par_iter().all(f)
+    } else {
+        items.iter().all(f)
+    }
+}
+
+#[allow(dead_code)]
+pub fn for_each_mut<T, F>(items: &mut [T], work: usize, f: F)
+where
+    T: Send,
+    F: Fn(&mut T) + Sync + Send,
+{
+    if parallel(work) {
+        items.par_iter_mut().for_each(f)
+    } else {
+        items.iter_mut().for_each(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frac::*;
+    use crate::padic::*;
+    use crate::surreal::*;
+
+    #[test]
+    fn testing_dispatch() {
+        // the only test that moves the threshold; results must not depend on it
+        let f = Frac {
+            numer: -7,
+            denom: 45,
+        };
+        let n = construct("{ 0, { 1 | 2 } | 3, { | 4 } }");
+        let run = || {
+            (
+                expand(&f, 101, 40).expanded,
+                negate(&n),
+                map_range(0..100, 100, |k| k * k),
+                join(10, || all(&[1, 2, 3], 3, |&k| k > 0), || 1),
+            )
+        };
+
+        set_parallel_threshold(0);
+        assert!(parallel(0));
+        let eager = run();
+        set_parallel_threshold(usize::MAX);
+        assert!(!parallel(1 << 40));
+        let lazy = run();
+        set_parallel_threshold(DEFAULT_THRESHOLD);
+        assert_eq!(parallel_threshold(), DEFAULT_THRESHOLD);
+
+        assert_eq!(eager, lazy);
+        assert_eq!(eager.1, construct("{ -3, { -4 | } | 0, { -2 | -1 } }"));
+        assert_eq!(eager.2[99], 9801);
+        assert_eq!(eager.3, (true, 1));
+    }
+}
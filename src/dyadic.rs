@@ -0,0 +1,421 @@
+use crate::frac::*;
+use core::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+// numer / 2^exp in lowest terms, numer is odd unless exp is 0; the fields are private so
+// that every value goes through new and stays reduced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dyadic {
+    numer: i64,
+    exp: u32,
+}
+
+#[allow(dead_code)]
+impl Dyadic {
+    pub fn new(numer: i64, exp: u32) -> Dyadic {
+        let shift = numer.trailing_zeros().min(exp);
+        Dyadic {
+            numer: numer >> shift,
+            exp: exp - shift,
+        }
+    }
+
+    pub fn integer(n: i64) -> Dyadic {
+        Dyadic { numer: n, exp: 0 }
+    }
+
+    pub fn numer(&self) -> i64 {
+        self.numer
+    }
+
+    pub fn exp(&self) -> u32 {
+        self.exp
+    }
+
+    // every finite f64 is dyadic, None when it does not fit in numer / 2^62
+    pub fn from_f64(x: f64) -> Option<Dyadic> {
+        if !x.is_finite() {
+            return None;
+        }
+        let mut scaled = x;
+        let mut exp = 0;
+        while scaled.fract() != 0.0 {
+            if exp == 62 {
+                return None;
+            }
+            scaled *= 2.0;
+            exp += 1;
+        }
+        if scaled.abs() >= 2f64.powi(63) {
+            return None;
+        }
+        Some(Dyadic::new(scaled as i64, exp))
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.numer as f64 / 2f64.powi(self.exp as i32)
+    }
+
+    // None when 2^exp does not fit in i64
+    pub fn to_frac(self) -> Option<Frac> {
+        Some(Frac {
+            numer: self.numer,
+            denom: 1i64.checked_shl(self.exp).filter(|&d| d > 0)?,
+        })
+    }
+
+    // None unless the reduced denominator is a power of 2
+    pub fn from_frac(f: &Frac) -> Option<Dyadic> {
+        let f = Frac::reduce(f.numer, f.denom);
+        if f.denom.count_ones() != 1 {
+            return None;
+        }
+        Some(Dyadic::new(f.numer, f.denom.trailing_zeros()))
+    }
+
+    // both numerators over the larger power of 2
+    fn aligned(&self, other: &Dyadic) -> (i128, i128, u32) {
+        let exp = self.exp.max(other.exp);
+        (
+            (self.numer as i128) << (exp - self.exp),
+            (other.numer as i128) << (exp - other.exp),
+            exp,
+        )
+    }
+
+    // the products of add and mul, whose numerator must fit in i64 and exponent stay below 63
+    fn from_i128(numer: i128, exp: u32) -> Dyadic {
+        let shift = numer.trailing_zeros().min(exp);
+        let numer = i64::try_from(numer >> shift).expect("dyadic numerator overflows i64");
+        assert!(exp - shift < 63, "dyadic denominator overflows i64");
+        Dyadic {
+            numer,
+            exp: exp - shift,
+        }
+    }
+
+    pub fn add(&self, other: &Dyadic) -> Dyadic {
+        let (a, b, exp) = self.aligned(other);
+        Dyadic::from_i128(a + b, exp)
+    }
+
+    pub fn neg(&self) -> Dyadic {
+        Dyadic {
+            numer: -self.numer,
+            exp: self.exp,
+        }
+    }
+
+    pub fn sub(&self, other: &Dyadic) -> Dyadic {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Dyadic) -> Dyadic {
+        Dyadic::from_i128(
+            self.numer as i128 * other.numer as i128,
+            self.exp + other.exp,
+        )
+    }
+
+    pub fn floor(&self) -> i64 {
+        self.numer >> self.exp
+    }
+
+    // the day on which the number is created: |n| for integers, floor(|x|) + 1 + exp otherwise
+    pub fn birthday(&self) -> u32 {
+        let n = self.numer.unsigned_abs();
+        if self.exp == 0 {
+            n as u32
+        } else {
+            (n >> self.exp) as u32 + 1 + self.exp
+        }
+    }
+
+    // the options of its simplest form: n + 1 = { n | }, -n - 1 = { | -n } and
+    // m / 2^k = { (m - 1) / 2^k | (m + 1) / 2^k } for odd m
+    pub fn options(&self) -> (Option<Dyadic>, Option<Dyadic>) {
+        match (self.exp, self.numer.signum()) {
+            (0, 0) => (None, None),
+            (0, 1) => (Some(Dyadic::integer(self.numer - 1)), None),
+            (0, _) => (None, Some(Dyadic::integer(self.numer + 1))),
+            _ => (
+                Some(Dyadic::new(self.numer - 1, self.exp)),
+                Some(Dyadic::new(self.numer + 1, self.exp)),
+            ),
+        }
+    }
+
+    // the simplicity theorem: the number of smallest birthday strictly between lo and hi, a
+    // missing bound being unbounded, None when lo >= hi
+    pub fn simplest_between(lo: Option<Dyadic>, hi: Option<Dyadic>) -> Option<Dyadic> {
+        let bound = |d: Dyadic| (d.numer as i128, 1i128 << d.exp);
+        simplest(lo.map(bound), hi.map(bound))
+    }
+
+    // the same between rationals, also None when the answer needs more than 63 halvings or
+    // does not fit in i64
+    pub fn simplest_between_fracs(lo: Option<Frac>, hi: Option<Frac>) -> Option<Dyadic> {
+        let bound = |f: Frac| {
+            let f = Frac::reduce(f.numer, f.denom);
+            (f.numer as i128, f.denom as i128)
+        };
+        simplest(lo.map(bound), hi.map(bound))
+    }
+
+    // the ancestor born on the given day in the binary tree of dyadics, itself if born by then
+    pub fn truncate(&self, day: u32) -> Dyadic {
+        let (mut lo, mut hi) = (None, None);
+        let mut current = Dyadic::integer(0);
+        for _ in 0..day {
+            if current == *self {
+                break;
+            }
+            if current < *self {
+                lo = Some(current);
+            } else {
+                hi = Some(current);
+            }
+            current = Dyadic::simplest_between(lo, hi).unwrap();
+        }
+        current
+    }
+}
+
+// numer / denom with denom > 0
+type Bound = (i128, i128);
+
+fn simplest(lo: Option<Bound>, hi: Option<Bound>) -> Option<Dyadic> {
+    let less = |(a, b): Bound, (c, d): Bound| a * d < c * b;
+    if let (Some(a), Some(b)) = (lo, hi) {
+        if !less(a, b) {
+            return None;
+        }
+    }
+    if lo.is_none_or(|(n, _)| n < 0) && hi.is_none_or(|(n, _)| n > 0) {
+        return Some(Dyadic::integer(0));
+    }
+    if hi.is_some_and(|(n, _)| n <= 0) {
+        let neg = |(n, d): Bound| (-n, d);
+        return simplest(hi.map(neg), lo.map(neg)).map(|x| x.neg());
+    }
+    // 0 <= lo: the smallest integer above lo, or else the first halving that fits
+    let (n, d) = lo.unwrap();
+    let floor = n.div_euclid(d);
+    let next = i64::try_from(floor + 1).ok()?;
+    let Some((hn, hd)) = hi.filter(|&b| !less((floor + 1, 1), b)) else {
+        return Some(Dyadic::integer(next));
+    };
+    // lo and hi less their integer part lie in [0, 1]
+    let (n, hn) = (n - floor * d, hn - floor * hd);
+    (1..=63)
+        .map(|k| ((n << k).div_euclid(d) + 1, k))
+        .find(|&(m, k)| m * hd < hn << k)
+        .and_then(|(m, k)| {
+            let numer = i64::try_from((floor << k) + m).ok()?;
+            Some(Dyadic::new(numer, k))
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseDyadicError {
+    // not an integer, a fraction n/m or a decimal
+    Invalid,
+    // a rational whose reduced denominator is not a power of 2, e.g. 1/3 or 0.1
+    NotDyadic,
+    // too many digits for i64
+    Overflow,
+}
+
+// integers, fractions such as 3/8 or 6/16 and decimals such as -2.75
+impl FromStr for Dyadic {
+    type Err = ParseDyadicError;
+
+    fn from_str(s: &str) -> Result<Dyadic, ParseDyadicError> {
+        let s = s.trim();
+        let integer = |t: &str| -> Result<i64, ParseDyadicError> {
+            let digits = t.strip_prefix(['-', '+']).unwrap_or(t);
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(ParseDyadicError::Invalid);
+            }
+            t.parse::<i64>().map_err(|_| ParseDyadicError::Overflow)
+        };
+        let f = if let Some((n, m)) = s.split_once('/') {
+            let (n, m) = (integer(n)?, integer(m)?);
+            if m == 0 {
+                return Err(ParseDyadicError::Invalid);
+            }
+            Frac { numer: n, denom: m }
+        } else if let Some((whole, decimals)) = s.split_once('.') {
+            let negative = whole.trim_start().starts_with('-');
+            let whole = if whole.is_empty() || whole == "-" || whole == "+" {
+                0
+            } else {
+                integer(whole)?
+            };
+            let scale = 10i64
+                .checked_pow(decimals.len() as u32)
+                .ok_or(ParseDyadicError::Overflow)?;
+            let fraction = if decimals.is_empty() {
+                0
+            } else if decimals.chars().all(|c| c.is_ascii_digit()) {
+                integer(decimals)?
+            } else {
+                return Err(ParseDyadicError::Invalid);
+            };
+            let numer = whole
+                .checked_mul(scale)
+                .and_then(|w| {
+                    if negative {
+                        w.checked_sub(fraction)
+                    } else {
+                        w.checked_add(fraction)
+                    }
+                })
+                .ok_or(ParseDyadicError::Overflow)?;
+            Frac {
+                numer,
+                denom: scale,
+            }
+        } else {
+            Frac {
+                numer: integer(s)?,
+                denom: 1,
+            }
+        };
+        Dyadic::from_frac(&f).ok_or(ParseDyadicError::NotDyadic)
+    }
+}
+
+impl Ord for Dyadic {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b, _) = self.aligned(other);
+        a.cmp(&b)
+    }
+}
+
+impl PartialOrd for Dyadic {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Dyadic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.exp == 0 {
+            write!(f, "{}", self.numer)
+        } else {
+            write!(f, "{}/{}", self.numer, 1u64 << self.exp)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(numer: i64, exp: u32) -> Dyadic {
+        Dyadic::new(numer, exp)
+    }
+
+    #[test]
+    fn testing_dyadic_arithmetic() {
+        assert_eq!(d(6, 2), d(3, 1));
+        assert_eq!(d(1, 1).add(&d(1, 1)), Dyadic::integer(1));
+        assert_eq!(d(3, 3).sub(&d(1, 1)), d(-1, 3));
+        assert_eq!(d(3, 2).mul(&d(-5, 1)), d(-15, 3));
+        assert!(d(-1, 1) < Dyadic::integer(0) && d(5, 3) < d(3, 1));
+        assert_eq!(d(-3, 1).floor(), -2);
+        assert_eq!(format!("{} {}", d(3, 3), d(-4, 1)), "3/8 -2");
+        assert_eq!(Dyadic::from_f64(-2.375), Some(d(-19, 3)));
+        assert_eq!(Dyadic::from_f64(0.1).map(|x| x.exp), Some(55));
+        assert_eq!(Dyadic::from_f64(f64::NAN), None);
+        assert_eq!(
+            Dyadic::from_frac(&Frac {
+                numer: 6,
+                denom: -16
+            }),
+            Some(d(-3, 3))
+        );
+        assert_eq!(Dyadic::from_frac(&Frac { numer: 1, denom: 3 }), None);
+        assert_eq!(d(3, 3).to_frac(), Some(Frac { numer: 3, denom: 8 }));
+        assert_eq!(
+            d(1, 62).to_frac(),
+            Some(Frac {
+                numer: 1,
+                denom: 1 << 62
+            })
+        );
+        assert_eq!(d(1, 63).to_frac(), None);
+        assert_eq!((d(12, 4).numer(), d(12, 4).exp()), (3, 2));
+        assert_eq!(d(3, 3).options(), (Some(d(1, 2)), Some(d(1, 1))));
+        assert_eq!(Dyadic::integer(-2).options(), (None, Some(d(-1, 0))));
+    }
+
+    #[test]
+    #[should_panic(expected = "dyadic denominator overflows i64")]
+    fn testing_exponent_overflow() {
+        d(1, 40).mul(&d(1, 40));
+    }
+
+    #[test]
+    fn testing_parse() {
+        assert_eq!("3/8".parse::<Dyadic>(), Ok(d(3, 3)));
+        assert_eq!(" -6/16 ".parse::<Dyadic>(), Ok(d(-3, 3)));
+        assert_eq!("1/-2".parse::<Dyadic>(), Ok(d(-1, 1)));
+        assert_eq!("7".parse::<Dyadic>(), Ok(Dyadic::integer(7)));
+        assert_eq!("-2.75".parse::<Dyadic>(), Ok(d(-11, 2)));
+        assert_eq!("-0.5".parse::<Dyadic>(), Ok(d(-1, 1)));
+        assert_eq!("2.0".parse::<Dyadic>(), Ok(Dyadic::integer(2)));
+        assert_eq!(".125".parse::<Dyadic>(), Ok(d(1, 3)));
+        assert_eq!("0.1".parse::<Dyadic>(), Err(ParseDyadicError::NotDyadic));
+        assert_eq!("1/3".parse::<Dyadic>(), Err(ParseDyadicError::NotDyadic));
+        assert_eq!("1/0".parse::<Dyadic>(), Err(ParseDyadicError::Invalid));
+        assert_eq!("x".parse::<Dyadic>(), Err(ParseDyadicError::Invalid));
+        assert_eq!("1.-5".parse::<Dyadic>(), Err(ParseDyadicError::Invalid));
+        assert_eq!(
+            "99999999999999999999".parse::<Dyadic>(),
+            Err(ParseDyadicError::Overflow)
+        );
+    }
+
+    #[test]
+    fn testing_simplicity() {
+        let between = |lo: Option<Dyadic>, hi: Option<Dyadic>| Dyadic::simplest_between(lo, hi);
+        let int = |n: i64| Some(Dyadic::integer(n));
+        assert_eq!(between(None, None), int(0));
+        assert_eq!(between(int(-3), int(7)), int(0));
+        assert_eq!(between(int(4), None), int(5));
+        assert_eq!(between(None, int(-20)), int(-21));
+        assert_eq!(between(int(0), int(1)), Some(d(1, 1)));
+        assert_eq!(between(Some(d(1, 1)), Some(d(3, 2))), Some(d(5, 3)));
+        assert_eq!(between(Some(d(1, 2)), Some(d(3, 2))), Some(d(1, 1)));
+        assert_eq!(between(Some(d(-3, 1)), Some(d(-9, 4))), int(-1));
+        assert_eq!(between(int(-3), Some(d(-5, 1))), Some(d(-11, 2)));
+        assert_eq!(between(Some(d(7, 3)), int(1)), Some(d(15, 4)));
+        assert_eq!(between(int(1), int(1)), None);
+        assert_eq!(between(int(2), int(1)), None);
+
+        assert_eq!(Dyadic::integer(-5).birthday(), 5);
+        assert_eq!(d(1, 1).birthday(), 2);
+        assert_eq!(d(-3, 2).birthday(), 3);
+        assert_eq!(d(11, 3).birthday(), 5);
+
+        let frac = |numer: i64, denom: i64| Some(Frac { numer, denom });
+        assert_eq!(
+            Dyadic::simplest_between_fracs(frac(1, 3), frac(1, 2)),
+            Some(d(3, 3))
+        );
+        assert_eq!(
+            Dyadic::simplest_between_fracs(frac(-5, 3), frac(-4, 3)),
+            Some(d(-3, 1))
+        );
+        assert_eq!(Dyadic::simplest_between_fracs(frac(1, 3), frac(1, 3)), None);
+        assert_eq!(Dyadic::simplest_between_fracs(None, frac(7, 2)), int(0));
+        // 5/16 is reached through 1, 1/2, 1/4 and 3/8
+        assert_eq!(d(5, 4).truncate(3), d(1, 2));
+        assert_eq!(d(5, 4).truncate(4), d(3, 3));
+        assert_eq!(d(5, 4).truncate(9), d(5, 4));
+        assert_eq!(Dyadic::integer(-7).truncate(2), Dyadic::integer(-2));
+    }
+}
@@ -0,0 +1,312 @@
+use crate::dispatch::*;
+use crate::factor::*;
+use crate::frac::*;
+use crate::padic::*;
+
+// φ(x) = P(x) / Q(x) over Q_p, coefficients from the constant term up
+#[derive(Debug, Clone)]
+pub struct RationalMap {
+    pub numer: Vec<Frac>,
+    pub denom: Vec<Frac>,
+    pub p: i64,
+}
+
+// by |λ|_p for the multiplier λ of a cycle: < 1, > 1 or = 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    Attracting,
+    Repelling,
+    Indifferent,
+}
+
+fn horner(coeffs: &[Frac], x: &Padic) -> Padic {
+    let c = |f: &Frac| expand(f, x.p, x.expanded.len());
+    let zero = c(&Frac { numer: 0, denom: 1 });
+    coeffs
+        .iter()
+        .rev()
+        .fold(zero, |acc, f| acc.mul(x).add(&c(f)))
+}
+
+fn derivative(coeffs: &[Frac]) -> Vec<Frac> {
+    coeffs
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| {
+            c.mul(&Frac {
+                numer: i as i64,
+                denom: 1,
+            })
+        })
+        .collect()
+}
+
+// f(r) mod m for p-integral coefficients, None when a denominator is divisible by p
+fn horner_mod(coeffs: &[Frac], r: i64, p: i64, m: i64) -> Option<i64> {
+    coeffs.iter().rev().try_fold(0i64, |acc, f| {
+        if f.denom % p == 0 {
+            return None;
+        }
+        let c = f.numer.rem_euclid(m) as i128 * mod_inverse(f.denom.rem_euclid(m), m)? as i128;
+        Some(((acc as i128 * r as i128 + c) % m as i128) as i64)
+    })
+}
+
+#[allow(dead_code)]
+impl RationalMap {
+    pub fn new(numer: &[Frac], denom: &[Frac], p: i64) -> RationalMap {
+        assert!(
+            denom.iter().any(|c| c.numer != 0),
+            "rational map with zero denominator"
+        );
+        RationalMap {
+            numer: numer.to_vec(),
+            denom: denom.to_vec(),
+            p,
+        }
+    }
+
+    pub fn polynomial(coeffs: &[Frac], p: i64) -> RationalMap {
+        RationalMap::new(coeffs, &[Frac { numer: 1, denom: 1 }], p)
+    }
+
+    // digit operations in evaluating P and Q at x, each product being quadratic in the digits
+    fn work(&self, x: &Padic) -> usize {
+        (self.numer.len() + self.denom.len()) * x.expanded.len().pow(2)
+    }
+
+    // None at a pole, i.e. when Q(x) is zero to the known precision
+    pub fn apply(&self, x: &Padic) -> Option<Padic> {
+        let (num, den) = join(
+            self.work(x),
+            || horner(&self.numer, x),
+            || horner(&self.denom, x),
+        );
+        num.div(&den)
+    }
+
+    // φ' = (P'Q - PQ') / Q^2
+    pub fn derivative_at(&self, x: &Padic) -> Option<Padic> {
+        let work = self.work(x);
+        let (num, den) = join(work, || horner(&self.numer, x), || horner(&self.denom, x));
+        let (dnum, dden) = join(
+            work,
+            || horner(&derivative(&self.numer), x),
+            || horner(&derivative(&self.denom), x),
+        );
+        dnum.mul(&den).sub(&num.mul(&dden)).div(&den.mul(&den))
+    }
+
+    // x, φ(x), φ(φ(x)), ... with n + 1 entries, cut short at a pole
+    pub fn orbit(&self, x: &Padic, n: usize) -> Vec<Padic> {
+        let mut orbit = vec![x.clone()];
+        for _ in 0..n {
+            match self.apply(orbit.last().unwrap()) {
+                Some(y) => orbit.push(y),
+                None => break,
+            }
+        }
+        orbit
+    }
+
+    // λ = φ'(x_1) ... φ'(x_n) over the points of a cycle
+    pub fn multiplier(&self, cycle: &[Padic]) -> Option<Padic> {
+        let work = cycle.iter().map(|x| 2 * self.work(x)).sum();
+        let derivatives: Option<Vec<Padic>> = map(cycle, work, |x| self.derivative_at(x))
+            .into_iter()
+            .collect();
+        derivatives?.into_iter().reduce(|acc, d| acc.mul(&d))
+    }
+
+    // a multiplier that is zero to the known precision counts as attracting
+    pub fn classify(&self, cycle: &[Padic]) -> Option<Stability> {
+        Some(match self.multiplier(cycle)?.valuation() {
+            None => Stability::Attracting,
+            Some(v) if v > 0 => Stability::Attracting,
+            Some(v) if v < 0 => Stability::Repelling,
+            Some(_) => Stability::Indifferent,
+        })
+    }
+
+    // the fixed points in Q_p, the roots of P(x) - xQ(x) found through factor
+    pub fn fixed_points(&self, precision: usize) -> Vec<(Padic, Stability)> {
+        let len = self.numer.len().max(self.denom.len() + 1);
+        let zero = Frac { numer: 0, denom: 1 };
+        let g: Vec<Frac> = (0..len)
+            .map(|i| {
+                let a = self.numer.get(i).copied().unwrap_or(zero);
+                let b = i
+                    .checked_sub(1)
+                    .and_then(|j| self.denom.get(j))
+                    .copied()
+                    .unwrap_or(zero);
+                a.sub(&b)
+            })
+            .collect();
+        if g.iter().skip(1).all(|c| c.numer == 0) {
+            return vec![];
+        }
+        factor(&g, self.p, precision)
+            .into_iter()
+            .filter(|f| f.coeffs.len() == 2)
+            .filter_map(|f| {
+                let x = f.coeffs[0].neg();
+                let stability = self.classify(std::slice::from_ref(&x))?;
+                Some((x, stability))
+            })
+            .collect()
+    }
+
+    // the cycles of the induced map on Z/p^n, each starting at its smallest residue; None
+    // when φ does not reduce to a map there, i.e. a coefficient is not p-integral or Q
+    // vanishes mod p somewhere
+    pub fn cycles_mod(&self, n: u32) -> Option<Vec<Vec<i64>>> {
+        let p = self.p;
+        let m = p.pow(n);
+        let work = m as usize * (self.numer.len() + self.denom.len());
+        let next: Option<Vec<i64>> = map_range(0..m, work, |r| {
+            let den = horner_mod(&self.denom, r, p, m)?;
+            if den % p == 0 {
+                return None;
+            }
+            let num = horner_mod(&self.numer, r, p, m)?;
+            Some((num as i128 * mod_inverse(den, m)? as i128 % m as i128) as i64)
+        })
+        .into_iter()
+        .collect();
+        let next = next?;
+
+        // every orbit of the functional graph ends in exactly one cycle
+        let mut state = vec![0u8; m as usize];
+        let mut cycles = Vec::new();
+        for start in 0..m as usize {
+            let mut path = Vec::new();
+            let mut r = start;
+            while state[r] == 0 {
+                state[r] = 1;
+                path.push(r);
+                r = next[r] as usize;
+            }
+            if state[r] == 1 {
+                let i = path.iter().position(|&x| x == r).unwrap();
+                let mut cycle: Vec<i64> = path[i..].iter().map(|&x| x as i64).collect();
+                let min = (0..cycle.len()).min_by_key(|&j| cycle[j]).unwrap();
+                cycle.rotate_left(min);
+                cycles.push(cycle);
+            }
+            path.iter().for_each(|&x| state[x] = 2);
+        }
+        cycles.sort();
+        Some(cycles)
+    }
+
+    // Newton's method on φ^k(x) - x from a residue of a cycle of length k, None when it does
+    // not converge, e.g. for cycles whose multiplier is 1 mod p
+    pub fn lift_cycle(&self, cycle: &[i64], precision: usize) -> Option<Vec<Padic>> {
+        let p = self.p;
+        let mut x = expand(
+            &Frac {
+                numer: cycle[0],
+                denom: 1,
+            },
+            p,
+            precision,
+        );
+        let one = expand(&Frac { numer: 1, denom: 1 }, p, precision);
+        for _ in 0..2 * precision + 4 {
+            let orbit = self.orbit(&x, cycle.len());
+            if orbit.len() <= cycle.len() {
+                return None;
+            }
+            let g = orbit[cycle.len()].sub(&x);
+            if g.valuation().is_none() {
+                return Some(orbit[..cycle.len()].to_vec());
+            }
+            let dg = self.multiplier(&orbit[..cycle.len()])?.sub(&one);
+            let step = g.div(&dg)?;
+            if step.valuation()? < 0 {
+                return None;
+            }
+            x = x.sub(&step);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(n: i64) -> Frac {
+        Frac { numer: n, denom: 1 }
+    }
+
+    #[test]
+    fn testing_fixed_points() {
+        // x^2 - 2 fixes 2 and -1 with φ' = 4 and -2, units in Z_3
+        let phi = RationalMap::polynomial(&[int(-2), int(0), int(1)], 3);
+        let fixed = phi.fixed_points(6);
+        assert_eq!(fixed.len(), 2);
+        for (x, stability) in &fixed {
+            assert_eq!(phi.apply(x).unwrap().sub(x).valuation(), None);
+            assert_eq!(*stability, Stability::Indifferent);
+        }
+
+        // but both multipliers lie in 2Z_2
+        let phi = RationalMap::polynomial(&[int(-2), int(0), int(1)], 2);
+        assert!(phi
+            .fixed_points(6)
+            .iter()
+            .all(|(_, s)| *s == Stability::Attracting));
+    }
+
+    #[test]
+    fn testing_rational_map() {
+        // φ(x) = 1 / (5x) is an involution, so its 2-cycles have multiplier 1
+        let phi = RationalMap::new(&[int(1)], &[int(0), int(5)], 5);
+        let x = expand(&int(3), 5, 6);
+        let orbit = phi.orbit(&x, 2);
+        assert_eq!(orbit[2].v, Some(int(3)));
+        assert_eq!(phi.classify(&orbit[..2]), Some(Stability::Indifferent));
+        assert!(phi.apply(&expand(&int(0), 5, 6)).is_none());
+
+        // x / 5 repels every point of Q_5
+        let phi = RationalMap::polynomial(&[int(0), Frac { numer: 1, denom: 5 }], 5);
+        assert_eq!(phi.fixed_points(6).len(), 1);
+        assert_eq!(phi.fixed_points(6)[0].1, Stability::Repelling);
+    }
+
+    #[test]
+    fn testing_cycles_mod() {
+        // x^2 on Z/5: 0 and 1 are fixed, 4 -> 1, and 2 -> 4, 3 -> 4
+        let phi = RationalMap::polynomial(&[int(0), int(0), int(1)], 5);
+        assert_eq!(phi.cycles_mod(1), Some(vec![vec![0], vec![1]]));
+        // on Z/25 squaring permutes the units of order 5
+        assert_eq!(
+            phi.cycles_mod(2),
+            Some(vec![vec![0], vec![1], vec![6, 11, 21, 16]])
+        );
+
+        // x^2 - 1 has the 2-cycle 0 -> -1 -> 0 in Z_3 with multiplier 0
+        let phi = RationalMap::polynomial(&[int(-1), int(0), int(1)], 3);
+        let cycles = phi.cycles_mod(1).unwrap();
+        assert!(cycles.contains(&vec![0, 2]));
+        let lifted = phi.lift_cycle(&[0, 2], 6).unwrap();
+        assert_eq!(lifted[0].valuation(), None);
+        assert_eq!(phi.classify(&lifted), Some(Stability::Attracting));
+
+        // x^2 + 2 fixes the root of x^2 - x + 2 that is 1 mod 2, with φ' = 2x
+        let phi = RationalMap::polynomial(&[int(2), int(0), int(1)], 2);
+        assert_eq!(phi.cycles_mod(1), Some(vec![vec![0], vec![1]]));
+        let root = phi.lift_cycle(&[1], 12).unwrap();
+        assert_eq!(root[0].expanded[0], 1);
+        assert!(root[0].precision() >= 10);
+        assert_eq!(phi.apply(&root[0]).unwrap().sub(&root[0]).valuation(), None);
+        assert_eq!(phi.classify(&root), Some(Stability::Attracting));
+
+        // a non-integral coefficient gives no map on Z/p^n
+        let phi = RationalMap::polynomial(&[Frac { numer: 1, denom: 3 }, int(1)], 3);
+        assert_eq!(phi.cycles_mod(2), None);
+    }
+}
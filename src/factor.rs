@@ -0,0 +1,960 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+
+// a monic factor over Q_p, coefficients from the constant term up
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct PadicFactor {
+    pub coeffs: Vec<Padic>,
+    pub multiplicity: usize,
+    // false when the Newton polygon cannot decide whether the factor splits further,
+    // which needs higher order polygons (e.g. (x^2 - 7)(x^2 - 14) over Q_7)
+    pub irreducible: bool,
+}
+
+// a factor over Z/p^prec during the recursion
+struct Block {
+    coeffs: Vec<i64>,
+    prec: u32,
+    irreducible: bool,
+}
+
+fn trim_frac(mut a: Vec<Frac>) -> Vec<Frac> {
+    while a.last().is_some_and(|c| c.numer == 0) {
+        a.pop();
+    }
+    a
+}
+
+fn gcd_int(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd_int(b, a % b)
+    }
+}
+
+fn trim_int(mut a: Vec<i128>) -> Vec<i128> {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+    a
+}
+
+// divided by the content, with a positive leading coefficient
+fn primitive(a: &[i128]) -> Vec<i128> {
+    let a = trim_int(a.to_vec());
+    let Some(&lc) = a.last() else {
+        return a;
+    };
+    let g = a.iter().fold(0, |g, &c| gcd_int(g, c)) * lc.signum();
+    a.iter().map(|&c| c / g).collect()
+}
+
+// the coefficients over a common denominator, None when they leave i128
+fn clear_denominators(f: &[Frac]) -> Option<Vec<i128>> {
+    let l = f.iter().try_fold(1i128, |l, c| {
+        let d = c.denom as i128;
+        (l / gcd_int(l, d)).checked_mul(d)
+    })?;
+    f.iter()
+        .map(|c| (c.numer as i128).checked_mul(l / c.denom as i128))
+        .collect()
+}
+
+fn derivative_int(a: &[i128]) -> Option<Vec<i128>> {
+    a.iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| c.checked_mul(i as i128))
+        .collect::<Option<Vec<i128>>>()
+        .map(trim_int)
+}
+
+fn sub_int(a: &[i128], b: &[i128]) -> Option<Vec<i128>> {
+    (0..a.len().max(b.len()))
+        .map(|i| a.get(i).unwrap_or(&0).checked_sub(*b.get(i).unwrap_or(&0)))
+        .collect::<Option<Vec<i128>>>()
+        .map(trim_int)
+}
+
+// the remainder of a by b up to a constant: each step takes lc(b) r - t x^k b, which stays in
+// Z[x], and divides out the content so the coefficients do not grow from step to step
+fn remainder_int(a: &[i128], b: &[i128]) -> Option<Vec<i128>> {
+    let mut r = primitive(a);
+    let lc = b[b.len() - 1];
+    while r.len() >= b.len() {
+        let shift = r.len() - b.len();
+        let t = r[r.len() - 1];
+        let g = gcd_int(lc, t);
+        let (lc, t) = (lc / g, t / g);
+        for c in r.iter_mut() {
+            *c = c.checked_mul(lc)?;
+        }
+        for (i, c) in b.iter().enumerate() {
+            r[i + shift] = r[i + shift].checked_sub(t.checked_mul(*c)?)?;
+        }
+        r = primitive(&r);
+    }
+    Some(r)
+}
+
+// the primitive remainder sequence
+fn gcd_poly_int(a: &[i128], b: &[i128]) -> Option<Vec<i128>> {
+    let (mut a, mut b) = (primitive(a), primitive(b));
+    while !b.is_empty() {
+        let r = remainder_int(&a, &b)?;
+        a = b;
+        b = r;
+    }
+    Some(a)
+}
+
+// a / b for a primitive b dividing a over Q, which by Gauss's lemma divides it over Z
+fn div_exact_int(a: &[i128], b: &[i128]) -> Option<Vec<i128>> {
+    let mut r = trim_int(a.to_vec());
+    if r.len() < b.len() {
+        return r.is_empty().then_some(vec![]);
+    }
+    let lc = b[b.len() - 1];
+    let mut q = vec![0; r.len() - b.len() + 1];
+    while r.len() >= b.len() {
+        let shift = r.len() - b.len();
+        let t = r[r.len() - 1];
+        if t % lc != 0 {
+            return None;
+        }
+        q[shift] = t / lc;
+        for (i, c) in b.iter().enumerate() {
+            r[i + shift] = r[i + shift].checked_sub(q[shift].checked_mul(*c)?)?;
+        }
+        r = trim_int(r);
+    }
+    r.is_empty().then_some(q)
+}
+
+// None when a monic coefficient does not fit a Frac
+fn monic_int(a: &[i128]) -> Option<Vec<Frac>> {
+    let lc = a[a.len() - 1];
+    a.iter()
+        .map(|&c| {
+            let g = gcd_int(c, lc) * lc.signum();
+            Some(Frac::reduce(
+                i64::try_from(c / g).ok()?,
+                i64::try_from(lc / g).ok()?,
+            ))
+        })
+        .collect()
+}
+
+// a trivial gcd(f, f') modulo a large prime proves f squarefree over Q without running
+// the remainder sequence
+fn is_squarefree(f: &[i128]) -> bool {
+    [1_000_000_007, 998_244_353].iter().any(|&q| {
+        let r: Vec<i64> = f.iter().map(|c| c.rem_euclid(q as i128) as i64).collect();
+        r.last() != Some(&0) && gcd(&r, &derivative(&r, q), q).len() == 1
+    })
+}
+
+// Yun's algorithm over Z[x] with primitive remainder sequences: the squarefree parts of f as
+// monic polynomials over Q with multiplicities, None when the coefficients leave i128
+fn squarefree_frac(f: &[Frac]) -> Option<Vec<(Vec<Frac>, usize)>> {
+    let f = primitive(&clear_denominators(f)?);
+    if is_squarefree(&f) {
+        return Some(vec![(monic_int(&f)?, 1)]);
+    }
+    let df = derivative_int(&f)?;
+    let a0 = gcd_poly_int(&f, &df)?;
+    let mut b = div_exact_int(&f, &a0)?;
+    let mut d = sub_int(&div_exact_int(&df, &a0)?, &derivative_int(&b)?)?;
+    let mut parts = Vec::new();
+    let mut i = 1;
+    while b.len() > 1 {
+        let a = gcd_poly_int(&b, &d)?;
+        b = div_exact_int(&b, &a)?;
+        d = sub_int(&div_exact_int(&d, &a)?, &derivative_int(&b)?)?;
+        if a.len() > 1 {
+            parts.push((monic_int(&a)?, i));
+        }
+        i += 1;
+    }
+    Some(parts)
+}
+
+fn mulmod(a: i64, b: i64, m: i64) -> i64 {
+    (a as i128 * b as i128).rem_euclid(m as i128) as i64
+}
+
+fn trim(mut a: Vec<i64>) -> Vec<i64> {
+    while a.last() == Some(&0) {
+        a.pop();
+    }
+    a
+}
+
+fn add(a: &[i64], b: &[i64], m: i64) -> Vec<i64> {
+    trim(
+        (0..a.len().max(b.len()))
+            .map(|i| (a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0)).rem_euclid(m))
+            .collect(),
+    )
+}
+
+fn sub(a: &[i64], b: &[i64], m: i64) -> Vec<i64> {
+    trim(
+        (0..a.len().max(b.len()))
+            .map(|i| (a.get(i).unwrap_or(&0) - b.get(i).unwrap_or(&0)).rem_euclid(m))
+            .collect(),
+    )
+}
+
+fn mul(a: &[i64], b: &[i64], m: i64) -> Vec<i64> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut c = vec![0i128; a.len() + b.len() - 1];
+    for (i, &x) in a.iter().enumerate() {
+        for (j, &y) in b.iter().enumerate() {
+            c[i + j] = (c[i + j] + x as i128 * y as i128) % m as i128;
+        }
+    }
+    trim(c.into_iter().map(|x| x as i64).collect())
+}
+
+fn scale(a: &[i64], c: i64, m: i64) -> Vec<i64> {
+    trim(a.iter().map(|&x| mulmod(x, c, m)).collect())
+}
+
+fn reduce(a: &[i64], m: i64) -> Vec<i64> {
+    trim(a.iter().map(|&x| x.rem_euclid(m)).collect())
+}
+
+// the leading coefficient of b must be a unit mod m
+fn divrem(a: &[i64], b: &[i64], m: i64) -> (Vec<i64>, Vec<i64>) {
+    let mut r = reduce(a, m);
+    if r.len() < b.len() {
+        return (vec![], r);
+    }
+    let inv = mod_inverse(b[b.len() - 1], m).expect("leading coefficient must be a unit");
+    let mut q = vec![0; r.len() - b.len() + 1];
+    while r.len() >= b.len() {
+        let shift = r.len() - b.len();
+        let t = mulmod(r[r.len() - 1], inv, m);
+        q[shift] = t;
+        for (i, &c) in b.iter().enumerate() {
+            r[i + shift] = (r[i + shift] - mulmod(t, c, m)).rem_euclid(m);
+        }
+        r = trim(r);
+    }
+    (trim(q), r)
+}
+
+fn monic(a: &[i64], m: i64) -> Vec<i64> {
+    match a.last() {
+        Some(&lc) => scale(a, mod_inverse(lc, m).unwrap(), m),
+        None => vec![],
+    }
+}
+
+fn derivative(a: &[i64], m: i64) -> Vec<i64> {
+    trim(
+        a.iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, &c)| mulmod(c, i as i64, m))
+            .collect(),
+    )
+}
+
+// monic gcd over F_p
+fn gcd(a: &[i64], b: &[i64], p: i64) -> Vec<i64> {
+    let (mut a, mut b) = (reduce(a, p), reduce(b, p));
+    while !b.is_empty() {
+        let r = divrem(&a, &b, p).1;
+        a = b;
+        b = r;
+    }
+    monic(&a, p)
+}
+
+// s with s*a = 1 mod b over F_p, a and b coprime
+fn inverse_mod(a: &[i64], b: &[i64], p: i64) -> Vec<i64> {
+    let (mut r0, mut r1) = (reduce(b, p), divrem(a, b, p).1);
+    let (mut s0, mut s1) = (vec![], vec![1]);
+    while !r1.is_empty() {
+        let (q, r) = divrem(&r0, &r1, p);
+        let s = sub(&s0, &mul(&q, &s1, p), p);
+        (r0, r1) = (r1, r);
+        (s0, s1) = (s1, s);
+    }
+    // r0 is a nonzero constant
+    let inv = mod_inverse(r0[0], p).unwrap();
+    divrem(&scale(&s0, inv, p), b, p).1
+}
+
+fn powmod(a: &[i64], mut e: u128, f: &[i64], p: i64) -> Vec<i64> {
+    let mut result = vec![1];
+    let mut base = divrem(a, f, p).1;
+    while e > 0 {
+        if e & 1 == 1 {
+            result = divrem(&mul(&result, &base, p), f, p).1;
+        }
+        base = divrem(&mul(&base, &base, p), f, p).1;
+        e >>= 1;
+    }
+    result
+}
+
+fn squarefree_fp(f: &[i64], p: i64) -> Vec<(Vec<i64>, usize)> {
+    let mut parts = Vec::new();
+    let mut c = gcd(f, &derivative(f, p), p);
+    let mut w = divrem(f, &c, p).0;
+    let mut i = 1;
+    while w.len() > 1 {
+        let y = gcd(&w, &c, p);
+        let fac = divrem(&w, &y, p).0;
+        if fac.len() > 1 {
+            parts.push((fac, i));
+        }
+        w = y;
+        c = divrem(&c, &w, p).0;
+        i += 1;
+    }
+    if c.len() > 1 {
+        // c is a polynomial in x^p, take its p-th root
+        let root: Vec<i64> = c.iter().step_by(p as usize).cloned().collect();
+        parts.extend(
+            squarefree_fp(&root, p)
+                .into_iter()
+                .map(|(g, j)| (g, j * p as usize)),
+        );
+    }
+    parts
+}
+
+fn distinct_degree(f: &[i64], p: i64) -> Vec<(Vec<i64>, usize)> {
+    let mut parts = Vec::new();
+    let mut f = f.to_vec();
+    let mut h = vec![0, 1];
+    let mut d = 1;
+    while f.len() > 2 * d {
+        h = powmod(&h, p as u128, &f, p);
+        let g = gcd(&f, &sub(&h, &[0, 1], p), p);
+        if g.len() > 1 {
+            f = divrem(&f, &g, p).0;
+            h = divrem(&h, &f, p).1;
+            parts.push((g, d));
+        }
+        d += 1;
+    }
+    if f.len() > 1 {
+        let d = f.len() - 1;
+        parts.push((f, d));
+    }
+    parts
+}
+
+// Cantor-Zassenhaus splitting of a product of irreducibles of degree d
+fn equal_degree(f: &[i64], d: usize, p: i64, seed: &mut u64) -> Vec<Vec<i64>> {
+    if f.len() - 1 == d {
+        return vec![f.to_vec()];
+    }
+    loop {
+        let a: Vec<i64> = (0..f.len() - 1)
+            .map(|_| {
+                *seed ^= *seed << 13;
+                *seed ^= *seed >> 7;
+                *seed ^= *seed << 17;
+                (*seed % p as u64) as i64
+            })
+            .collect();
+        let a = trim(a);
+        if a.len() < 2 {
+            continue;
+        }
+        let b = if p == 2 {
+            // trace map a + a^2 + ... + a^(2^(d-1))
+            let mut t = a.clone();
+            let mut power = a.clone();
+            for _ in 1..d {
+                power = powmod(&power, 2, f, p);
+                t = add(&t, &power, p);
+            }
+            t
+        } else {
+            let e = ((p as u128).pow(d as u32) - 1) / 2;
+            sub(&powmod(&a, e, f, p), &[1], p)
+        };
+        let g = gcd(f, &b, p);
+        if g.len() > 1 && g.len() < f.len() {
+            let h = divrem(f, &g, p).0;
+            let mut parts = equal_degree(&g, d, p, seed);
+            parts.extend(equal_degree(&h, d, p, seed));
+            return parts;
+        }
+    }
+}
+
+// monic irreducible factors over F_p with multiplicities
+fn factor_fp(f: &[i64], p: i64) -> Vec<(Vec<i64>, usize)> {
+    let mut seed = 0x2545_f491_4f6c_dd1d;
+    let mut factors = Vec::new();
+    for (g, e) in squarefree_fp(&monic(f, p), p) {
+        for (h, d) in distinct_degree(&g, p) {
+            for q in equal_degree(&h, d, p, &mut seed) {
+                factors.push((q, e));
+            }
+        }
+    }
+    factors
+}
+
+// lifts k = g*h mod p to k = G*H mod p^n, g monic and coprime to h mod p
+fn hensel_split(k: &[i64], g: &[i64], h: &[i64], p: i64, n: u32) -> (Vec<i64>, Vec<i64>) {
+    let m = p.pow(n);
+    let s = inverse_mod(h, g, p);
+    let (mut gg, mut hh) = (g.to_vec(), h.to_vec());
+    let mut pk = p;
+    for _ in 1..n {
+        let e = sub(k, &mul(&gg, &hh, m), m);
+        let e: Vec<i64> = trim(e.iter().map(|&c| (c / pk) % p).collect());
+        let dg = divrem(&mul(&e, &s, p), g, p).1;
+        let dh = divrem(&sub(&e, &mul(h, &dg, p), p), g, p).0;
+        gg = add(&gg, &scale(&dg, pk, m), m);
+        hh = add(&hh, &scale(&dh, pk, m), m);
+        pk *= p;
+    }
+    (gg, hh)
+}
+
+// multifactor Hensel lifting of a monic k whose reduction is the product of coprime factors
+fn hensel_lift(k: &[i64], factors: &[Vec<i64>], p: i64, n: u32) -> Vec<Vec<i64>> {
+    if factors.len() == 1 {
+        return vec![k.to_vec()];
+    }
+    let rest = factors[1..].iter().fold(vec![1], |acc, f| mul(&acc, f, p));
+    let (g, h) = hensel_split(k, &factors[0], &rest, p, n);
+    let mut lifted = vec![g];
+    lifted.extend(hensel_lift(&h, &factors[1..], p, n));
+    lifted
+}
+
+// f(x + a) mod m
+fn taylor_shift(f: &[i64], a: i64, m: i64) -> Vec<i64> {
+    f.iter()
+        .rev()
+        .fold(vec![], |acc, &c| add(&mul(&acc, &[a, 1], m), &[c], m))
+}
+
+fn residue_valuation(c: i64, p: i64) -> Option<u32> {
+    if c == 0 {
+        return None;
+    }
+    let mut c = c;
+    let mut k = 0;
+    while c % p == 0 {
+        c /= p;
+        k += 1;
+    }
+    Some(k)
+}
+
+// vertices of the lower convex hull of points sorted by x
+fn lower_hull(points: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut hull: Vec<(i64, i64)> = Vec::new();
+    for &pt in points {
+        while hull.len() >= 2 {
+            let (o, a) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+            let cross = (a.0 - o.0) * (pt.1 - o.1) - (a.1 - o.1) * (pt.0 - o.0);
+            if cross > 0 {
+                break;
+            }
+            hull.pop();
+        }
+        hull.push(pt);
+    }
+    hull
+}
+
+// vertices (i, v_p(a_i)) of the Newton polygon of a_0 + a_1 x + ... + a_n x^n
+#[allow(dead_code)]
+pub fn newton_polygon(coeffs: &[Padic]) -> Vec<(i64, i64)> {
+    let points: Vec<(i64, i64)> = coeffs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.valuation().map(|v| (i as i64, v)))
+        .collect();
+    lower_hull(&points)
+}
+
+// F(x) -> p^(k deg F) F(x / p^k)
+fn unscale(f: &[i64], k: u32, p: i64, m: i64) -> Vec<i64> {
+    let d = f.len() - 1;
+    f.iter()
+        .enumerate()
+        .map(|(j, &c)| mulmod(c, mod_pow(p, (k as usize * (d - j)) as i64, m), m))
+        .collect()
+}
+
+fn shifted_back(blocks: Vec<Block>, a: i64, p: i64) -> Vec<Block> {
+    blocks
+        .into_iter()
+        .map(|b| {
+            let m = p.pow(b.prec);
+            Block {
+                coeffs: taylor_shift(&b.coeffs, (m - a).rem_euclid(m), m),
+                ..b
+            }
+        })
+        .collect()
+}
+
+// lifting a block of degree d to prec digits takes about d^2 prec digit operations
+fn block_work(g: &[i64], prec: u32) -> usize {
+    g.len().pow(2) * prec as usize
+}
+
+fn factor_block(g: Vec<i64>, prec: u32, p: i64) -> Vec<Block> {
+    let undecided = |g: Vec<i64>| {
+        vec![Block {
+            coeffs: g,
+            prec,
+            irreducible: false,
+        }]
+    };
+    let n = g.len() - 1;
+    if n == 1 {
+        return vec![Block {
+            coeffs: g,
+            prec,
+            irreducible: true,
+        }];
+    }
+    if prec == 0 {
+        return undecided(g);
+    }
+    let m = p.pow(prec);
+    let factors = factor_fp(&reduce(&g, p), p);
+
+    if factors.len() > 1 {
+        let blocks: Vec<Vec<i64>> = factors
+            .iter()
+            .map(|(phi, e)| (0..*e).fold(vec![1], |acc, _| mul(&acc, phi, p)))
+            .collect();
+        let lifted = hensel_lift(&g, &blocks, p, prec);
+        let work = lifted.iter().map(|b| block_work(b, prec)).sum();
+        return map(&lifted, work, |b| factor_block(b.clone(), prec, p))
+            .into_iter()
+            .flatten()
+            .collect();
+    }
+    let (phi, e) = &factors[0];
+    if *e == 1 {
+        return vec![Block {
+            coeffs: g,
+            prec,
+            irreducible: true,
+        }];
+    }
+    if phi.len() > 2 {
+        return undecided(g);
+    }
+
+    // the reduction is (x - a)^n, move the root to 0 and read the Newton polygon
+    let a = (p - phi[0]) % p;
+    let h = taylor_shift(&g, a, m);
+    let v: Vec<Option<u32>> = (0..=n)
+        .map(|j| residue_valuation(*h.get(j).unwrap_or(&0), p))
+        .collect();
+    // a coefficient that vanishes to the known precision has valuation at least prec
+    let v: Vec<u32> = v.into_iter().map(|vj| vj.unwrap_or(prec)).collect();
+    let points: Vec<(i64, i64)> = v
+        .iter()
+        .enumerate()
+        .map(|(j, &vj)| (j as i64, vj as i64))
+        .collect();
+    let hull = lower_hull(&points);
+    // root valuations, from the largest (first segment) to the smallest
+    let slopes: Vec<(i64, i64)> = hull
+        .windows(2)
+        .map(|w| (w[0].1 - w[1].1, w[1].0 - w[0].0))
+        .collect();
+
+    let k: i64 = if slopes.len() == 1 {
+        let (height, length) = slopes[0];
+        if Frac::gcd(height, length) == 1 {
+            // no lattice points inside the segment: generalized Eisenstein criterion
+            return vec![Block {
+                coeffs: g,
+                prec,
+                irreducible: true,
+            }];
+        }
+        if height % length != 0 {
+            return undecided(g);
+        }
+        height / length
+    } else {
+        let (min_h, min_l) = slopes[slopes.len() - 1];
+        let (max_h, max_l) = slopes[0];
+        let k = min_h / min_l + 1;
+        if k * max_l > max_h {
+            // no integer separates the slopes
+            return undecided(g);
+        }
+        k
+    };
+    let k = k as u32;
+
+    // K(y) = H(p^k y) / p^shift is primitive
+    let shift = v
+        .iter()
+        .enumerate()
+        .map(|(j, vj)| vj + k * j as u32)
+        .min()
+        .unwrap();
+    if shift >= prec {
+        return undecided(g);
+    }
+    let kprec = prec - shift;
+    let km = p.pow(kprec);
+    let kpoly: Vec<i64> = (0..=n)
+        .map(|j| {
+            let c = *h.get(j).unwrap_or(&0);
+            let t = k * j as u32;
+            if t >= shift {
+                mulmod(c, mod_pow(p, (t - shift) as i64, km), km)
+            } else {
+                (c / p.pow(shift - t)).rem_euclid(km)
+            }
+        })
+        .collect();
+
+    if slopes.len() == 1 {
+        let blocks = factor_block(trim(kpoly), kprec, p)
+            .into_iter()
+            .map(|b| Block {
+                coeffs: unscale(&b.coeffs, k, p, p.pow(b.prec)),
+                ..b
+            })
+            .collect();
+        return shifted_back(blocks, a, p);
+    }
+
+    // split off the roots of valuation >= k, whose part of the reduction is monic
+    let kbar = reduce(&kpoly, p);
+    let lc = kbar[kbar.len() - 1];
+    let (big, small) = hensel_split(&trim(kpoly), &monic(&kbar, p), &[lc], p, kprec);
+    let big = unscale(&big, k, p, km);
+    let small = trim(small);
+    let d = small.len() - 1;
+    let w = residue_valuation(small[d], p).unwrap();
+    if w >= kprec {
+        return undecided(g);
+    }
+    let sprec = kprec - w;
+    let sm = p.pow(sprec);
+    let unit = mod_inverse(small[d] / p.pow(w), sm).unwrap();
+    let small: Vec<i64> = small
+        .iter()
+        .enumerate()
+        .map(|(j, &c)| {
+            let c = mulmod(c, mod_pow(p, (k as usize * (d - j)) as i64, km), km);
+            mulmod(c / p.pow(w), unit, sm)
+        })
+        .collect();
+    let parts = vec![
+        (taylor_shift(&big, (km - a).rem_euclid(km), km), kprec),
+        (taylor_shift(&small, (sm - a).rem_euclid(sm), sm), sprec),
+    ];
+    let work = parts.iter().map(|(f, prec)| block_work(f, *prec)).sum();
+    map(&parts, work, |(f, prec)| factor_block(f.clone(), *prec, p))
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn to_padic(r: i64, p: i64, precision: usize, offset: i64) -> Padic {
+    let mut r = r;
+    let digits = (0..precision)
+        .map(|_| {
+            let d = r % p;
+            r /= p;
+            d
+        })
+        .collect();
+    Padic::with_digits(p, digits, offset, None)
+}
+
+// factors a polynomial with rational coefficients (constant term first) over Q_p into
+// monic factors known to the given number of digits; the working modulus is the largest
+// power of p below 2^62, and precision lost along the way shortens the digit vectors.
+// When the squarefree split over Q leaves i128, f comes back whole, monic and undecided
+#[allow(dead_code)]
+pub fn factor(f: &[Frac], p: i64, precision: usize) -> Vec<PadicFactor> {
+    let f = trim_frac(f.to_vec());
+    assert!(f.len() > 1, "cannot factor a constant");
+    let mut work = 1;
+    while p.pow(work) < (1 << 62) / p {
+        work += 1;
+    }
+    let m = p.pow(work);
+
+    let Some(parts) = squarefree_frac(&f) else {
+        let lc = expand(&f[f.len() - 1], p, precision);
+        return vec![PadicFactor {
+            coeffs: f
+                .iter()
+                .map(|c| {
+                    expand(c, p, precision)
+                        .div(&lc)
+                        .expect("a nonzero coefficient is invertible in Q_p")
+                })
+                .collect(),
+            multiplicity: 1,
+            irreducible: false,
+        }];
+    };
+    let cost = parts
+        .iter()
+        .map(|(q, _)| q.len().pow(2) * work as usize)
+        .sum();
+    map(&parts, cost, |(q, multiplicity)| {
+        // q(x) = p^(-kn) q~(p^k x) with q~ monic over Z_p
+        let n = q.len() - 1;
+        let k = q
+            .iter()
+            .enumerate()
+            .take(n)
+            .filter_map(|(j, c)| c.valuation(p).map(|v| (-v, (n - j) as i64)))
+            .map(|(neg, len)| (neg + len - 1).div_euclid(len))
+            .max()
+            .unwrap_or(0)
+            .max(0);
+        let residues: Vec<i64> = q
+            .iter()
+            .enumerate()
+            .map(|(j, c)| match c.valuation(p) {
+                None => 0,
+                Some(v) => {
+                    let vp = p.pow(v.unsigned_abs() as u32);
+                    let unit = if v >= 0 {
+                        Frac::reduce(c.numer / vp, c.denom)
+                    } else {
+                        Frac::reduce(c.numer, c.denom / vp)
+                    };
+                    let r = mulmod(unit.numer, mod_inverse(unit.denom, m).unwrap(), m);
+                    mulmod(r, mod_pow(p, v + k * (n - j) as i64, m), m)
+                }
+            })
+            .collect();
+        factor_block(residues, work, p)
+            .into_iter()
+            .map(|b| {
+                let d = b.coeffs.len() - 1;
+                let digits = precision.min(b.prec as usize);
+                PadicFactor {
+                    coeffs: b
+                        .coeffs
+                        .iter()
+                        .enumerate()
+                        .map(|(j, &c)| to_padic(c, p, digits, k * (j as i64 - d as i64)))
+                        .collect(),
+                    multiplicity: *multiplicity,
+                    irreducible: b.irreducible,
+                }
+            })
+            .collect::<Vec<PadicFactor>>()
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frac_int(n: i64) -> Frac {
+        Frac { numer: n, denom: 1 }
+    }
+
+    fn ints(c: &[i64]) -> Vec<Frac> {
+        c.iter().map(|&n| frac_int(n)).collect()
+    }
+
+    fn constant_term(f: &PadicFactor) -> Padic {
+        f.coeffs[0].clone()
+    }
+
+    #[test]
+    fn testing_modular_factorization() {
+        // x^4 - 1 = (x - 1)(x + 1)(x^2 + 1) over F_3
+        let mut factors = factor_fp(&[2, 0, 0, 0, 1], 3);
+        factors.sort();
+        assert_eq!(
+            factors,
+            vec![(vec![1, 0, 1], 1), (vec![1, 1], 1), (vec![2, 1], 1)]
+        );
+        // (x + 1)^2 x over F_2
+        let factors = factor_fp(&[0, 1, 0, 1], 2);
+        assert_eq!(factors.len(), 2);
+        assert!(factors.contains(&(vec![1, 1], 2)));
+    }
+
+    #[test]
+    fn testing_hensel_roots() {
+        // x^2 - 2 splits over Q_7 since 3^2 = 2 mod 7
+        let factors = factor(&ints(&[-2, 0, 1]), 7, 10);
+        assert_eq!(factors.len(), 2);
+        for f in &factors {
+            assert!(f.irreducible);
+            assert_eq!(f.coeffs.len(), 2);
+            let root = constant_term(f).neg();
+            assert_eq!(
+                root.mul(&root).expanded,
+                expand(&frac_int(2), 7, 10).expanded
+            );
+        }
+    }
+
+    #[test]
+    fn testing_multiplicities() {
+        // (x - 1)^2 (x^2 + 1) over Q_5
+        let f = ints(&[1, -2, 2, -2, 1]);
+        let factors = factor(&f, 5, 8);
+        assert_eq!(factors.len(), 3);
+        assert_eq!(factors.iter().filter(|f| f.multiplicity == 2).count(), 1);
+        let double = factors.iter().find(|f| f.multiplicity == 2).unwrap();
+        assert_eq!(
+            constant_term(double).expanded,
+            expand(&frac_int(-1), 5, 8).expanded
+        );
+
+        // (x^2 - 101)^3 (x^2 + 11x + 37)^2, whose Euclidean remainders over Q leave i64
+        let f = ints(&[
+            -1410482069,
+            -838665014,
+            -159013188,
+            2244220,
+            4522477,
+            426624,
+            -27113,
+            -5852,
+            -108,
+            22,
+            1,
+        ]);
+        let factors = factor(&f, 5, 6);
+        let roots: Vec<&PadicFactor> = factors.iter().filter(|f| f.multiplicity == 3).collect();
+        assert_eq!(roots.len(), 2);
+        for root in roots {
+            let x = constant_term(root);
+            assert_eq!(x.mul(&x).expanded, expand(&frac_int(101), 5, 6).expanded);
+        }
+        let quadratic = factors.iter().find(|f| f.multiplicity == 2).unwrap();
+        assert_eq!(quadratic.coeffs.len(), 3);
+        assert_eq!(
+            constant_term(quadratic).expanded,
+            expand(&frac_int(37), 5, 6).expanded
+        );
+        assert_eq!(factors.len(), 3);
+    }
+
+    #[test]
+    fn testing_newton_polygon_splitting() {
+        // (x - 5)(x - 25): both roots reduce to 0 but have valuations 1 and 2
+        let factors = factor(&ints(&[125, -30, 1]), 5, 6);
+        assert_eq!(factors.len(), 2);
+        let mut valuations: Vec<i64> = factors
+            .iter()
+            .map(|f| constant_term(f).valuation().unwrap())
+            .collect();
+        valuations.sort();
+        assert_eq!(valuations, vec![1, 2]);
+        assert!(factors.iter().all(|f| f.irreducible));
+
+        // x^2 - 50 = 25 (y^2 - 2) and 2 is not a square mod 5
+        let factors = factor(&ints(&[-50, 0, 1]), 5, 6);
+        assert_eq!(factors.len(), 1);
+        assert!(factors[0].irreducible);
+
+        // Eisenstein
+        let factors = factor(&ints(&[-7, 0, 1]), 7, 6);
+        assert_eq!(factors.len(), 1);
+        assert!(factors[0].irreducible);
+
+        let polygon = newton_polygon(&[
+            expand(&frac_int(125), 5, 6),
+            expand(&frac_int(-30), 5, 6),
+            expand(&frac_int(1), 5, 6),
+        ]);
+        assert_eq!(polygon, vec![(0, 3), (1, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn testing_rational_coefficients() {
+        // 3x^2 - 1/7 has the monic factor x^2 - 1/21
+        let f = vec![
+            Frac {
+                numer: -1,
+                denom: 7,
+            },
+            frac_int(0),
+            frac_int(3),
+        ];
+        let factors = factor(&f, 7, 6);
+        assert_eq!(factors.len(), 1);
+        assert!(factors[0].irreducible);
+        let c = constant_term(&factors[0]);
+        assert_eq!(c.valuation(), Some(-1));
+        assert_eq!(
+            c.normalized().expanded[..5],
+            expand(
+                &Frac {
+                    numer: -1,
+                    denom: 21
+                },
+                7,
+                5
+            )
+            .expanded[..]
+        );
+    }
+
+    #[test]
+    fn testing_product_recovers_polynomial() {
+        for (f, p) in [
+            (vec![3, -2, 0, 5, 7, 1], 3),
+            (vec![-1, 0, 0, 0, 1], 2),
+            (vec![-6, 11, -6, 1], 2),
+            (vec![-8, 0, 0, 1], 2),
+        ] {
+            let product = factor(&ints(&f), p, 6).iter().fold(
+                vec![expand(&frac_int(1), p, 6)],
+                |acc, factor| {
+                    let zero = expand(&frac_int(0), p, 6);
+                    let mut c = vec![zero; acc.len() + factor.coeffs.len() - 1];
+                    for (i, x) in acc.iter().enumerate() {
+                        for (j, y) in factor.coeffs.iter().enumerate() {
+                            c[i + j] = c[i + j].add(&x.mul(y));
+                        }
+                    }
+                    c
+                },
+            );
+            for (c, &n) in product.iter().zip(&f) {
+                assert_eq!(c.expanded[..6], expand(&frac_int(n), p, 6).expanded[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn testing_undecided() {
+        // (x^2 - 7)(x^2 - 14): one segment of slope 1/2 with an interior lattice point
+        let factors = factor(&ints(&[98, 0, -21, 0, 1]), 7, 6);
+        assert_eq!(factors.len(), 1);
+        assert!(!factors[0].irreducible);
+    }
+}
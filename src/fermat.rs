@@ -0,0 +1,148 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+
+// sieve of Eratosthenes, the primes below limit
+#[allow(dead_code)]
+pub fn primes(limit: i64) -> Vec<i64> {
+    let n = limit.max(0) as usize;
+    let mut composite = vec![false; n];
+    let mut i = 2;
+    while i * i < n {
+        if !composite[i] {
+            (i * i..n).step_by(i).for_each(|j| composite[j] = true);
+        }
+        i += 1;
+    }
+    (2..n)
+        .filter(|&i| !composite[i])
+        .map(|i| i as i64)
+        .collect()
+}
+
+// q_p(a) = (a^(p-1) - 1) / p mod p, read off a^(p-1) mod p^2; None when p divides a
+#[allow(dead_code)]
+pub fn fermat_quotient(a: i64, p: i64) -> Option<i64> {
+    if a % p == 0 {
+        return None;
+    }
+    let x = mod_pow(a, p - 1, p * p);
+    Some((x - 1).rem_euclid(p * p) / p)
+}
+
+// the integer (a^(p-1) - 1) / p itself in Z_q to the given precision, by q-adic arithmetic
+// on the digits of a so that no modulus has to fit in an i64; one extra digit covers the
+// division when q = p. None when p divides a
+#[allow(dead_code)]
+pub fn fermat_quotient_padic(a: i64, p: i64, q: i64, precision: usize) -> Option<Padic> {
+    if a % p == 0 {
+        return None;
+    }
+    let int = |n: i64| expand(&Frac { numer: n, denom: 1 }, q, precision + 1);
+    let quotient = int(a).pow(p - 1).sub(&int(1)).div(&int(p))?;
+    let mut digits = vec![0; quotient.offset.max(0) as usize];
+    digits.extend(quotient.expanded);
+    digits.resize(precision, 0);
+    Some(Padic::with_digits(q, digits, 0, quotient.v))
+}
+
+// a^(p-1) mod p^2 by repeated squaring, a step per bit of each p
+fn quotients_work(primes: &[i64]) -> usize {
+    primes.iter().map(|p| 64 - p.leading_zeros() as usize).sum()
+}
+
+// (p, q_p(a)) for the primes p < limit not dividing a, computed in parallel
+#[allow(dead_code)]
+pub fn fermat_quotients(a: i64, limit: i64) -> Vec<(i64, i64)> {
+    let primes = primes(limit);
+    map(&primes, quotients_work(&primes), |&p| {
+        Some((p, fermat_quotient(a, p)?))
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+// the base a Wieferich primes p < limit, a^(p-1) = 1 mod p^2, each with the 2-adic digits of
+// its Fermat quotient (a^(p-1) - 1) / p, which is divisible by p
+#[allow(dead_code)]
+pub fn wieferich_primes(a: i64, limit: i64, precision: usize) -> Vec<(i64, Padic)> {
+    let primes = primes(limit);
+    let found: Vec<i64> = map(&primes, quotients_work(&primes), |&p| {
+        (fermat_quotient(a, p) == Some(0)).then_some(p)
+    })
+    .into_iter()
+    .flatten()
+    .collect();
+    // each power of a takes a multiplication of precision^2 per bit of p
+    let work = quotients_work(&found) * precision.pow(2);
+    map(&found, work, |&p| {
+        Some((p, fermat_quotient_padic(a, p, 2, precision)?))
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testing_fermat_quotients() {
+        assert_eq!(primes(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        // (2^4 - 1) / 5 = 3, (3^6 - 1) / 7 = 104 = 6 mod 7
+        assert_eq!(fermat_quotient(2, 5), Some(3));
+        assert_eq!(fermat_quotient(3, 7), Some(6));
+        assert_eq!(fermat_quotient(7, 7), None);
+        let quotients = fermat_quotients(2, 100);
+        assert_eq!(quotients.len(), 24);
+        assert!(quotients.iter().all(|&(p, q)| (0..p).contains(&q)));
+
+        // 104 = 0b1101000 in Z_2, and 104 = 2 * 49 + 6 in Z_7
+        let q = fermat_quotient_padic(3, 7, 2, 8).unwrap();
+        assert_eq!(q.expanded, vec![0, 0, 0, 1, 0, 1, 1, 0]);
+        assert_eq!(
+            q.v,
+            Some(Frac {
+                numer: 104,
+                denom: 1
+            })
+        );
+        let q = fermat_quotient_padic(3, 7, 7, 4).unwrap();
+        assert_eq!(q.expanded, vec![6, 0, 2, 0]);
+    }
+
+    #[test]
+    fn testing_wieferich_primes() {
+        let found = wieferich_primes(2, 4000, 16);
+        let found_primes: Vec<i64> = found.iter().map(|(p, _)| *p).collect();
+        assert_eq!(found_primes, vec![1093, 3511]);
+        // (2^1092 - 1) / 1093 is odd
+        assert_eq!(found[0].1.expanded[0], 1);
+        // more digits find the same primes and extend the same expansions
+        let precise = wieferich_primes(2, 4000, 60);
+        assert_eq!(precise.len(), 2);
+        for ((p, q), (p60, q60)) in found.iter().zip(&precise) {
+            assert_eq!(p, p60);
+            assert_eq!(q60.expanded.len(), 60);
+            assert_eq!(q60.expanded[..16], q.expanded[..]);
+        }
+
+        assert_eq!(
+            wieferich_primes(5, 50000, 8)
+                .iter()
+                .map(|(p, _)| *p)
+                .collect::<Vec<_>>(),
+            vec![2, 20771, 40487]
+        );
+        // 3^10 = 59049 = 1 mod 121
+        assert_eq!(
+            wieferich_primes(3, 1000, 8)
+                .iter()
+                .map(|(p, _)| *p)
+                .collect::<Vec<_>>(),
+            vec![11]
+        );
+    }
+}
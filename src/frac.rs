@@ -6,16 +6,77 @@ pub struct Frac {
     pub denom: i64,
 }
 
+#[allow(dead_code)]
 impl Frac {
     pub fn add(&self, other: &Frac) -> Frac {
-        let (nn, nd) = rayon::join(
-            || self.numer * other.denom + other.numer * self.denom,
-            || self.denom * other.denom,
-        );
-        Frac::reduce(nn, nd)
+        // a handful of i64 operations, far below what a rayon task costs
+        Frac::reduce(
+            self.numer * other.denom + other.numer * self.denom,
+            self.denom * other.denom,
+        )
+    }
+
+    pub fn neg(&self) -> Frac {
+        Frac {
+            numer: -self.numer,
+            denom: self.denom,
+        }
+    }
+
+    pub fn sub(&self, other: &Frac) -> Frac {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Frac) -> Frac {
+        Frac::reduce(self.numer * other.numer, self.denom * other.denom)
+    }
+
+    pub fn recip(&self) -> Frac {
+        Frac::reduce(self.denom, self.numer)
+    }
+
+    pub fn div(&self, other: &Frac) -> Frac {
+        self.mul(&other.recip())
+    }
+
+    // the checked variants return None instead of overflowing i64
+    pub fn checked_add(&self, other: &Frac) -> Option<Frac> {
+        let nn = (self.numer.checked_mul(other.denom)?)
+            .checked_add(other.numer.checked_mul(self.denom)?)?;
+        let nd = self.denom.checked_mul(other.denom)?;
+        Some(Frac::reduce(nn, nd))
+    }
+
+    pub fn checked_mul(&self, other: &Frac) -> Option<Frac> {
+        let nn = self.numer.checked_mul(other.numer)?;
+        let nd = self.denom.checked_mul(other.denom)?;
+        Some(Frac::reduce(nn, nd))
+    }
+
+    pub fn checked_div(&self, other: &Frac) -> Option<Frac> {
+        if other.numer == 0 {
+            return None;
+        }
+        self.checked_mul(&other.recip())
+    }
+
+    // exponent of p in the fraction, None for zero
+    pub fn valuation(&self, p: i64) -> Option<i64> {
+        if self.numer == 0 {
+            return None;
+        }
+        let count = |mut n: i64| {
+            let mut k = 0;
+            while n % p == 0 {
+                n /= p;
+                k += 1;
+            }
+            k
+        };
+        Some(count(self.numer) - count(self.denom))
     }
 
-    fn gcd(mut a: i64, mut b: i64) -> i64 {
+    pub fn gcd(mut a: i64, mut b: i64) -> i64 {
         while b != 0 {
             let temp = b;
             b = a % b;
@@ -24,11 +85,12 @@ impl Frac {
         a.abs()
     }
 
-    fn reduce(numer: i64, denom: i64) -> Frac {
+    pub fn reduce(numer: i64, denom: i64) -> Frac {
         let g = Frac::gcd(numer, denom);
+        let sign = if denom < 0 { -1 } else { 1 };
         Frac {
-            numer: numer / g,
-            denom: denom / g,
+            numer: sign * numer / g,
+            denom: sign * denom / g,
         }
     }
 }
@@ -41,9 +103,9 @@ impl fmt::Display for Frac {
 
 impl PartialEq for Frac {
     fn eq(&self, other: &Self) -> bool {
-        let (rs, ro) = rayon::join(
-            || Frac::reduce(self.numer, self.denom),
-            || Frac::reduce(other.numer, other.denom),
+        let (rs, ro) = (
+            Frac::reduce(self.numer, self.denom),
+            Frac::reduce(other.numer, other.denom),
         );
         rs.numer == ro.numer && rs.denom == ro.denom
     }
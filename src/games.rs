@@ -0,0 +1,226 @@
+use crate::dispatch::*;
+use crate::dyadic::*;
+use crate::surreal::*;
+use std::collections::HashSet;
+
+// a canonical game as an option of another one, numbers being leaves as in canonical
+fn as_option(g: &Surreal) -> SurrealValue {
+    match value(g) {
+        Some(d) => SurrealValue::Dyadic(d),
+        None => SurrealValue::Surreal(g.clone()),
+    }
+}
+
+// one more than the latest birthday among the options, 0 for { | }
+fn form_birthday(n: &Surreal) -> u32 {
+    [&n.l, &n.r]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|v| match v {
+            SurrealValue::Dyadic(d) => d.birthday() + 1,
+            SurrealValue::Surreal(s) => form_birthday(s) + 1,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// the first day on which a game of this value is created, which is the day of its canonical
+// form: 0 for 0, 1 for 1, -1 and *, 2 for 1/2 and up
+#[allow(dead_code)]
+pub fn birthday(n: &Surreal) -> u32 {
+    form_birthday(&canonical(n))
+}
+
+// the 2^(day + 1) - 1 numbers born by the given day in increasing order, in simplest form;
+// each day adds n + 1 above the largest, n - 1 below the smallest and the midpoints between
+// neighbours
+#[allow(dead_code)]
+pub fn numbers_born_by(day: u32) -> Vec<Surreal> {
+    let mut numbers = vec![Dyadic::integer(0)];
+    for _ in 0..day {
+        let (first, last) = (numbers[0], numbers[numbers.len() - 1]);
+        let mut next = vec![first.sub(&Dyadic::integer(1))];
+        for w in numbers.windows(2) {
+            next.push(w[0]);
+            next.push(w[0].add(&w[1]).mul(&Dyadic::new(1, 1)));
+        }
+        next.push(last);
+        next.push(last.add(&Dyadic::integer(1)));
+        numbers = next;
+    }
+    numbers.into_iter().map(from_dyadic).collect()
+}
+
+// the sets of pairwise confused or incomparable games, as indices into games, which are the
+// possible option sides of canonical forms since a dominated option is never canonical
+fn antichains(games: &[Surreal]) -> Vec<Vec<usize>> {
+    let n = games.len();
+    let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (0..n).map(move |j| (i, j))).collect();
+    let work = pairs
+        .iter()
+        .map(|&(i, j)| size(&games[i]) + size(&games[j]))
+        .sum();
+    let le_table = map(&pairs, work, |&(i, j)| le(&games[i], &games[j]));
+    let comparable = |i: usize, j: usize| le_table[i * n + j] || le_table[j * n + i];
+
+    let mut chains: Vec<Vec<usize>> = vec![vec![]];
+    for i in 0..n {
+        let extended: Vec<Vec<usize>> = chains
+            .iter()
+            .filter(|chain| chain.iter().all(|&j| !comparable(i, j)))
+            .map(|chain| chain.iter().copied().chain([i]).collect())
+            .collect();
+        chains.extend(extended);
+    }
+    chains
+}
+
+// every game born by the given day in canonical form, one per value: 1, 4, 22 and 1474 of them
+// for days 0 to 3. The games of day n are the forms { L | R } with L and R antichains of the
+// games of day n - 1, canonicalized in parallel and deduplicated; sorted by birthday, then by
+// how they print
+#[allow(dead_code)]
+pub fn games_born_by(day: u32) -> Vec<Surreal> {
+    if day == 0 {
+        return vec![zero()];
+    }
+    let previous = games_born_by(day - 1);
+    let options: Vec<SurrealValue> = previous.iter().map(as_option).collect();
+    let sides = antichains(&previous);
+    let pairs: Vec<(&Vec<usize>, &Vec<usize>)> = sides
+        .iter()
+        .flat_map(|l| sides.iter().map(move |r| (l, r)))
+        .collect();
+    let pick = |side: &Vec<usize>| {
+        (!side.is_empty()).then(|| side.iter().map(|&i| options[i].clone()).collect())
+    };
+    let work = pairs.len() * previous.iter().map(size).sum::<usize>();
+    let forms = map(&pairs, work, |&(l, r)| {
+        canonical(&Surreal {
+            l: pick(l),
+            r: pick(r),
+        })
+    });
+
+    // canonical forms are equal exactly when their values are, so forms are compared directly
+    let mut seen = HashSet::new();
+    let mut games: Vec<Surreal> = forms
+        .into_iter()
+        .filter(|g| seen.insert(Form(SurrealValue::Surreal(g.clone()))))
+        .collect();
+    games.sort_by_cached_key(|g| (form_birthday(g), g.to_string()));
+    games
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pstar::star;
+
+    #[test]
+    fn testing_birthday() {
+        assert_eq!(birthday(&zero()), 0);
+        assert_eq!(birthday(&star(1)), 1);
+        assert_eq!(birthday(&construct("{ 0 | 1 }")), 2);
+        assert_eq!(birthday(&construct("{ 0 | { 0 | 0 } }")), 2);
+        // forms born late for values born early: { -1, 0 | 1, 2 } = 1/2 and *2 + *3 = *
+        assert_eq!(birthday(&construct("{ -1, 0 | 1, 2 }")), 2);
+        assert_eq!(birthday(&add(&star(2), &star(3))), 1);
+        assert_eq!(birthday(&star(3)), 3);
+        assert_eq!(birthday(&from_dyadic(Dyadic::new(-11, 3))), 5);
+    }
+
+    #[test]
+    fn testing_numbers_born_by() {
+        for day in 0..5 {
+            let numbers = numbers_born_by(day);
+            assert_eq!(numbers.len(), (1 << (day + 1)) - 1);
+            assert!(numbers.windows(2).all(|w| lt(&w[0], &w[1])));
+            assert!(numbers.iter().all(|x| birthday(x) <= day));
+        }
+        let values: Vec<Dyadic> = numbers_born_by(2)
+            .iter()
+            .map(|x| value(x).unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            ["-2", "-1", "-1/2", "0", "1/2", "1", "2"].map(|s| s.parse::<Dyadic>().unwrap())
+        );
+    }
+
+    #[test]
+    fn testing_games_born_by() {
+        let counts: Vec<usize> = (0..4).map(|day| games_born_by(day).len()).collect();
+        assert_eq!(counts, [1, 4, 22, 1474]);
+
+        let day2 = games_born_by(2);
+        assert_eq!(
+            day2.iter().filter(|g| value(g).is_some()).count(),
+            numbers_born_by(2).len()
+        );
+        assert!(day2
+            .iter()
+            .all(|g| structural_eq(&canonical(g), g) && birthday(g) <= 2));
+        assert!(day2.contains(&construct("{ 0 | { 0 | 0 } }")));
+        assert!(day2.contains(&construct("{ 1 | -1 }")));
+    }
+
+    // properties of add, negate and the order over every pair and triple of games born by
+    // day 2, sums being compared through their canonical forms
+    #[test]
+    fn testing_properties() {
+        let games = games_born_by(2);
+        let n = games.len();
+        let values: HashSet<Surreal> = games.iter().cloned().collect();
+        let days: Vec<u32> = games.iter().map(birthday).collect();
+
+        for x in &games {
+            let minus = negate(x);
+            assert!(structural_eq(&negate(&minus), x));
+            assert!(values.contains(&minus));
+            assert_eq!(add(x, &minus), zero());
+            assert!(structural_eq(&canonical(&add(x, &zero())), x));
+            assert_eq!(game_cmp(x, x), GameOrdering::Equal);
+        }
+
+        let mut order = vec![vec![GameOrdering::Equal; n]; n];
+        let mut sums = vec![vec![zero(); n]; n];
+        for (i, x) in games.iter().enumerate() {
+            for (j, y) in games.iter().enumerate() {
+                order[i][j] = game_cmp(x, y);
+                sums[i][j] = canonical(&add(x, y));
+            }
+        }
+        let le =
+            |i: usize, j: usize| matches!(order[i][j], GameOrdering::Less | GameOrdering::Equal);
+        for (i, x) in games.iter().enumerate() {
+            for (j, y) in games.iter().enumerate() {
+                assert_eq!(order[j][i], order[i][j].reverse());
+                // distinct canonical forms are distinct values
+                assert_eq!(order[i][j] == GameOrdering::Equal, i == j);
+                assert_eq!(game_cmp(&negate(y), &negate(x)), order[i][j]);
+                assert!(structural_eq(&sums[i][j], &sums[j][i]));
+                assert!(structural_eq(
+                    &canonical(&negate(&sums[i][j])),
+                    &canonical(&add(&negate(x), &negate(y)))
+                ));
+                assert!(birthday(&sums[i][j]) <= days[i] + days[j]);
+
+                for (k, z) in games.iter().enumerate() {
+                    if le(i, j) && le(j, k) {
+                        assert!(le(i, k));
+                    }
+                    // adding z keeps the order, and sums with games born by day 1 associate
+                    if days[k] <= 1 {
+                        assert_eq!(game_cmp(&sums[i][k], &sums[j][k]), order[i][j]);
+                        assert!(structural_eq(
+                            &canonical(&add(&sums[i][j], z)),
+                            &canonical(&add(x, &sums[j][k]))
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+use crate_padic::Padic as CratePadic;
+use crate_padic::Ratio;
+
+// The padic crate keeps the prime of its Padic private and has no constructor other than
+// Ratio::to_padic, so its expansions convert into ours given the prime, while ours can only go
+// back through their rational value.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    // the value does not fit in an i64, or in the u64 fields of Ratio
+    Overflow,
+    // a digit of an external expansion is not below p
+    InvalidDigit,
+    // the digits carry no exact rational value, e.g. after an operation left i64
+    Inexact,
+}
+
+impl From<Frac> for Ratio {
+    fn from(f: Frac) -> Ratio {
+        let f = Frac::reduce(f.numer, f.denom);
+        Ratio {
+            numer: f.numer.unsigned_abs(),
+            denom: f.denom.unsigned_abs(),
+            sign: if f.numer < 0 { -1 } else { 1 },
+        }
+    }
+}
+
+impl TryFrom<Ratio> for Frac {
+    type Error = ConversionError;
+
+    fn try_from(r: Ratio) -> Result<Frac, ConversionError> {
+        let numer = i64::try_from(r.numer).map_err(|_| ConversionError::Overflow)?;
+        let denom = i64::try_from(r.denom).map_err(|_| ConversionError::Overflow)?;
+        Ok(Frac::reduce(r.sign * numer, denom))
+    }
+}
+
+// through the rational value v the digits carry
+impl TryFrom<&Padic> for Ratio {
+    type Error = ConversionError;
+
+    fn try_from(x: &Padic) -> Result<Ratio, ConversionError> {
+        x.v.map(Ratio::from).ok_or(ConversionError::Inexact)
+    }
+}
+
+// the external expansion with the prime it was made for
+impl TryFrom<(&CratePadic, i64)> for Padic {
+    type Error = ConversionError;
+
+    fn try_from((x, p): (&CratePadic, i64)) -> Result<Padic, ConversionError> {
+        let expanded = x
+            .expansion
+            .iter()
+            .map(|&d| {
+                i64::try_from(d)
+                    .ok()
+                    .filter(|&d| d < p)
+                    .ok_or(ConversionError::InvalidDigit)
+            })
+            .collect::<Result<Vec<i64>, ConversionError>>()?;
+        Ok(Padic::with_digits(p, expanded, x.valuation, None))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Agreement {
+    Agree,
+    // the smallest power of p at which the digits differ, with our digit and theirs
+    Differ {
+        position: i64,
+        ours: i64,
+        theirs: i64,
+    },
+    // Ratio::to_padic panics, e.g. on every positive integer
+    Panicked,
+    // outside what Ratio::to_padic accepts: zero, numerator or denominator above 2^20,
+    // primes above 32749
+    Unsupported,
+}
+
+// Ratio::to_padic looks for a digit d in 0..=p and 1 <= n <= b with a / b = d - p n / b for the
+// p-free part a / b, and then only meets remainders of that same -n / b form, so it panics
+// exactly when the first step has no such d
+fn to_padic_panics(f: &Frac, p: i64) -> bool {
+    let f = Frac::reduce(f.numer, f.denom);
+    let (mut a, mut b) = (f.numer as i128, f.denom as i128);
+    while a % p as i128 == 0 {
+        a /= p as i128;
+    }
+    while b % p as i128 == 0 {
+        b /= p as i128;
+    }
+    let p = p as i128;
+    !(0..=p).any(|d| {
+        let n = d * b - a;
+        n % p == 0 && (p..=p * b).contains(&n)
+    })
+}
+
+// compares digits at the same powers of p over the range both expansions know
+#[allow(dead_code)]
+pub fn first_difference(ours: &Padic, theirs: &CratePadic) -> Agreement {
+    let ours = ours.normalized();
+    let digit = |offset: i64, digits: &[i64], k: i64| {
+        if k < offset {
+            Some(0)
+        } else {
+            digits.get((k - offset) as usize).copied()
+        }
+    };
+    let theirs_digits: Vec<i64> = theirs.expansion.iter().map(|&d| d as i64).collect();
+    let start = ours.offset.min(theirs.valuation);
+    let end = ours
+        .precision()
+        .min(theirs.valuation + theirs_digits.len() as i64);
+    for k in start..end {
+        let (a, b) = (
+            digit(ours.offset, &ours.expanded, k),
+            digit(theirs.valuation, &theirs_digits, k),
+        );
+        if let (Some(a), Some(b)) = (a, b) {
+            if a != b {
+                return Agreement::Differ {
+                    position: k,
+                    ours: a,
+                    theirs: b,
+                };
+            }
+        }
+    }
+    Agreement::Agree
+}
+
+// expands every value with expand and with Ratio::to_padic, in parallel
+#[allow(dead_code)]
+pub fn differential_check(values: &[Frac], p: i64, precision: usize) -> Vec<(Frac, Agreement)> {
+    // both expansions try up to p candidate digits for each digit
+    let work = precision * p as usize;
+    map(values, values.len() * work, |&f| {
+        let r = Ratio::from(f);
+        if f.numer == 0 || r.numer > 1 << 20 || r.denom > 1 << 20 || p > 32749 {
+            return (f, Agreement::Unsupported);
+        }
+        if to_padic_panics(&f, p) {
+            return (f, Agreement::Panicked);
+        }
+        let (ours, theirs) = join(
+            work,
+            || expand(&f, p, precision),
+            || std::panic::catch_unwind(|| r.to_padic(p as u64, precision as u64)),
+        );
+        match theirs {
+            Ok(theirs) => (f, first_difference(&ours, &theirs)),
+            Err(_) => (f, Agreement::Panicked),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testing_conversions() {
+        let r = Ratio::from(Frac {
+            numer: 6,
+            denom: -4,
+        });
+        assert_eq!(r, Ratio::new(-3, 2));
+        assert_eq!(
+            Frac::try_from(r),
+            Ok(Frac {
+                numer: -3,
+                denom: 2
+            })
+        );
+        let big = Ratio {
+            numer: u64::MAX,
+            denom: 1,
+            sign: 1,
+        };
+        assert_eq!(Frac::try_from(big), Err(ConversionError::Overflow));
+
+        let x = expand(
+            &Frac {
+                numer: 2,
+                denom: 45,
+            },
+            3,
+            6,
+        );
+        assert_eq!(Ratio::try_from(&x), Ok(Ratio::new(2, 45)));
+        // (1/3)^45 does not fit in an i64, and the digits alone have no rational value
+        assert_eq!(
+            Ratio::try_from(&expand(&Frac { numer: 1, denom: 3 }, 7, 30).pow(45)),
+            Err(ConversionError::Inexact)
+        );
+
+        let theirs = Ratio::new(2, 45).to_padic(3, 5);
+        let ours = Padic::try_from((&theirs, 3)).unwrap();
+        assert_eq!(ours.offset, -2);
+        assert_eq!(ours.expanded, vec![1, 1, 2, 1, 0]);
+        assert_eq!(
+            ours.expanded,
+            expand(
+                &Frac {
+                    numer: 2,
+                    denom: 45
+                },
+                3,
+                7
+            )
+            .normalized()
+            .expanded[..5]
+        );
+        assert_eq!(
+            Padic::try_from((&theirs, 2)).map(|x| x.offset),
+            Err(ConversionError::InvalidDigit)
+        );
+    }
+
+    #[test]
+    fn testing_differential_check() {
+        let values: Vec<Frac> = (-30..30)
+            .flat_map(|n| (1..12).map(move |d| Frac { numer: n, denom: d }))
+            .collect();
+        for p in [2, 3, 5, 7] {
+            for (f, agreement) in differential_check(&values, p, 8) {
+                if f.numer == 0 {
+                    assert_eq!(agreement, Agreement::Unsupported);
+                } else if to_padic_panics(&f, p) {
+                    assert_eq!(agreement, Agreement::Panicked);
+                } else {
+                    assert_eq!(agreement, Agreement::Agree, "{} in Q_{}", f, p);
+                }
+            }
+        }
+
+        assert!(to_padic_panics(&Frac { numer: 7, denom: 1 }, 5));
+        assert!(to_padic_panics(
+            &Frac {
+                numer: -15,
+                denom: 1
+            },
+            2
+        ));
+        assert!(!to_padic_panics(
+            &Frac {
+                numer: -1,
+                denom: 3
+            },
+            2
+        ));
+
+        // a tampered digit is found at its power of p
+        let f = Frac {
+            numer: 7,
+            denom: 75,
+        };
+        let mut theirs = Ratio::from(f).to_padic(5, 6);
+        theirs.expansion[3] = (theirs.expansion[3] + 1) % 5;
+        let ours = expand(&f, 5, 8);
+        assert!(matches!(
+            first_difference(&ours, &theirs),
+            Agreement::Differ { position: 1, .. }
+        ));
+    }
+}
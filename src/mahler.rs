@@ -0,0 +1,155 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+
+// f(x) = sum a_n C(x, n), the coefficients known mod p^precision
+#[derive(Debug, Clone)]
+pub struct Mahler {
+    pub p: i64,
+    pub coefficients: Vec<Padic>,
+}
+
+// a_n = sum (-1)^(n - k) C(n, k) f(k) is the n-th forward difference of f at 0, taken on the
+// digits so that p^precision never has to fit an integer
+#[allow(dead_code)]
+pub fn from_table(values: &[i64], p: i64, precision: usize) -> Mahler {
+    let mut row = map(values, values.len() * precision * p as usize, |&f| {
+        expand(&Frac { numer: f, denom: 1 }, p, precision)
+    });
+    let mut coefficients = Vec::with_capacity(values.len());
+    while let Some(first) = row.first() {
+        coefficients.push(first.clone());
+        row = row.windows(2).map(|w| w[1].sub(&w[0])).collect();
+    }
+    Mahler { p, coefficients }
+}
+
+// the first `terms` coefficients of f restricted to 0..terms
+#[allow(dead_code)]
+pub fn from_fn<F>(f: F, terms: usize, p: i64, precision: usize) -> Mahler
+where
+    F: Fn(i64) -> i64 + Sync,
+{
+    let values: Vec<i64> = map_range(0..terms as i64, terms, &f);
+    from_table(&values, p, precision)
+}
+
+#[allow(dead_code)]
+impl Mahler {
+    pub fn valuations(&self) -> Vec<Option<i64>> {
+        self.coefficients.iter().map(|a| a.valuation()).collect()
+    }
+
+    // f extends continuously to Z_p exactly when a_n -> 0; from finitely many coefficients
+    // this checks that the tail vanishes or that the smallest valuation in the second half
+    // exceeds the smallest valuation in the first half
+    pub fn tends_to_zero(&self) -> bool {
+        let min = |a: &[Padic]| a.iter().filter_map(|x| x.valuation()).min();
+        let (head, tail) = self.coefficients.split_at(self.coefficients.len() / 2);
+        match (min(head), min(tail)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(h), Some(t)) => t > h,
+        }
+    }
+
+    // sum a_n C(x, n) over the known coefficients, the binomials built up as
+    // C(x, n) = C(x, n - 1)(x - n + 1) / n so that the precision lost to n! is tracked. The
+    // missing a_n are taken to be as divisible by p as the smallest in the second half of the
+    // known ones, as in tends_to_zero, and the digits they could change are dropped
+    pub fn evaluate(&self, x: &Padic) -> Padic {
+        assert_eq!(self.p, x.p, "p-adic numbers with different primes");
+        let precision = x.expanded.len();
+        let int = |n: i64| expand(&Frac { numer: n, denom: 1 }, self.p, precision);
+        let mut binomial = int(1);
+        let mut sum = int(0);
+        for (n, a) in self.coefficients.iter().enumerate() {
+            if n > 0 {
+                binomial = binomial
+                    .mul(&x.sub(&int(n as i64 - 1)))
+                    .div(&int(n as i64))
+                    .unwrap();
+            }
+            sum = sum.add(&a.mul(&binomial));
+        }
+        let tail = self.coefficients[self.coefficients.len() / 2..]
+            .iter()
+            .filter_map(|a| a.valuation())
+            .min();
+        match tail {
+            Some(v) if v < sum.precision() => {
+                let digits = (v - sum.offset).max(0) as usize;
+                Padic::with_digits(
+                    self.p,
+                    sum.expanded[..digits].to_vec(),
+                    sum.offset.min(v),
+                    None,
+                )
+            }
+            _ => sum,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testing_polynomials() {
+        // n^2 = 2 C(n, 2) + C(n, 1)
+        let m = from_fn(|n| n * n, 8, 3, 6);
+        let a: Vec<Frac> = m.coefficients.iter().map(|a| a.v.unwrap()).collect();
+        assert_eq!(a[..3], [0, 1, 2].map(|n| Frac { numer: n, denom: 1 }));
+        assert!(m.valuations()[3..].iter().all(|v| v.is_none()));
+        assert!(m.tends_to_zero());
+
+        let x = expand(&Frac { numer: 1, denom: 2 }, 3, 8);
+        let y = m.evaluate(&x);
+        assert_eq!(
+            y.expanded[..6],
+            expand(&Frac { numer: 1, denom: 4 }, 3, 6).expanded[..]
+        );
+
+        // 3^40 and 10^20 do not fit an i64
+        let m = from_fn(|n| n * n * n, 8, 3, 40);
+        assert_eq!(m.coefficients[3].v, Some(Frac { numer: 6, denom: 1 }));
+        assert!(m.coefficients.iter().all(|a| a.expanded.len() == 40));
+        let m = from_fn(|n| 1 - n, 4, 10, 20);
+        assert_eq!(m.coefficients[1].expanded, vec![9; 20]);
+        assert_eq!(m.valuations()[2..], [None, None]);
+    }
+
+    #[test]
+    fn testing_convergence() {
+        // (-1)^n has coefficients (-2)^n, continuous on Z_2 but not on Z_3
+        let sign = |n: i64| if n % 2 == 0 { 1 } else { -1 };
+        assert!(from_fn(sign, 16, 2, 20).tends_to_zero());
+        assert!(!from_fn(sign, 16, 3, 10).tends_to_zero());
+    }
+
+    #[test]
+    fn testing_gamma_interpolation() {
+        // Γ_5 on 0..40, (-1)^n times the product of the units below n, mod 5^6
+        let m = 5i64.pow(6);
+        let values: Vec<i64> = (0..40)
+            .map(|n: i64| {
+                let unit = (1..n).filter(|j| j % 5 != 0).fold(1, |acc, j| acc * j % m);
+                if n % 2 == 0 {
+                    unit
+                } else {
+                    -unit
+                }
+            })
+            .collect();
+        let mahler = from_table(&values, 5, 6);
+        assert!(mahler.tends_to_zero());
+
+        let x = expand(&Frac { numer: 1, denom: 2 }, 5, 20);
+        let y = mahler.evaluate(&x);
+        let gamma = padic_gamma(&expand(&Frac { numer: 1, denom: 2 }, 5, 6));
+        // the known coefficients end at valuation 4, and every digit claimed is right
+        assert_eq!(y.precision(), 4);
+        assert_eq!(y.expanded[..], gamma.expanded[..4]);
+    }
+}
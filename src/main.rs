@@ -1,7 +1,23 @@
+mod combinatorics;
+mod continued;
+mod dispatch;
+mod dyadic;
+mod dynamics;
+mod factor;
+mod fermat;
 mod frac;
+mod games;
+mod interop;
+mod mahler;
+mod matrix;
 mod padic;
 mod pstar;
+mod quadratic;
+mod series;
 mod surreal;
+mod zeta;
+use crate::dyadic::*;
+use crate::fermat::*;
 use crate::frac::*;
 use crate::padic::*;
 use crate::pstar::*;
@@ -23,8 +39,8 @@ fn create_complex_par_surreal() -> Surreal {
         let mut right_set: Vec<SurrealValue> = vec![];
 
         for j in 0..5 {
-            let left_value = SurrealValue::Float(i as f64);
-            let right_value = SurrealValue::Float((i * 5 + j) as f64);
+            let left_value = SurrealValue::Dyadic(Dyadic::integer(i));
+            let right_value = SurrealValue::Dyadic(Dyadic::integer(i * 5 + j));
 
             let left_surreal = Surreal {
                 l: Some(vec![left_value.clone()]),
@@ -42,7 +58,7 @@ fn create_complex_par_surreal() -> Surreal {
         }
 
         let nested_surreal = Surreal {
-            l: Some(vec![SurrealValue::Float(i as f64)]),
+            l: Some(vec![SurrealValue::Dyadic(Dyadic::integer(i))]),
             r: Some(right_set),
         };
 
@@ -50,13 +66,8 @@ fn create_complex_par_surreal() -> Surreal {
     }
 
     Surreal {
-        l: Some(vec![SurrealValue::Float(0.0)]),
-        r: Some(
-            nested_set
-                .into_iter()
-                .map(|s| SurrealValue::Surreal(s))
-                .collect(),
-        ),
+        l: Some(vec![SurrealValue::Dyadic(Dyadic::integer(0))]),
+        r: Some(nested_set.into_iter().map(SurrealValue::Surreal).collect()),
     }
 }
 
@@ -105,7 +116,7 @@ fn main() {
     let start = Instant::now();
     let zero = zero();
     let one = Surreal {
-        l: Some(vec![SurrealValue::Float(0.0)]),
+        l: Some(vec![SurrealValue::Dyadic(Dyadic::integer(0))]),
         r: None,
     };
     let _ = lt(&zero, &one);
@@ -207,4 +218,12 @@ fn main() {
         "Time taken for par padic to perform addition with 10 precision: {:.6?}",
         elapsed
     );
+
+    let start = Instant::now();
+    let _ = wieferich_primes(2, 1_000_000, 32);
+    let elapsed = start.elapsed();
+    println!(
+        "Time taken for par Wieferich search below 10^6: {:.6?}",
+        elapsed
+    );
 }
@@ -0,0 +1,345 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+
+#[derive(Debug, Clone)]
+pub struct PadicMatrix {
+    pub p: i64,
+    pub entries: Vec<Vec<Padic>>,
+}
+
+#[allow(dead_code)]
+pub fn from_fracs(m: &[Vec<Frac>], p: i64, precision: usize) -> PadicMatrix {
+    let work = m.iter().map(Vec::len).sum::<usize>() * precision * p as usize;
+    let entries = map(m, work, |row| {
+        row.iter().map(|f| expand(f, p, precision)).collect()
+    });
+    PadicMatrix { p, entries }
+}
+
+#[allow(dead_code)]
+pub fn from_integers(m: &[Vec<i64>], p: i64, precision: usize) -> PadicMatrix {
+    let fracs: Vec<Vec<Frac>> = m
+        .iter()
+        .map(|row| row.iter().map(|&n| Frac { numer: n, denom: 1 }).collect())
+        .collect();
+    from_fracs(&fracs, p, precision)
+}
+
+fn constant(n: i64, p: i64, precision: usize) -> Padic {
+    expand(&Frac { numer: n, denom: 1 }, p, precision)
+}
+
+// index of the entry with the smallest valuation, None when all are zero to precision
+fn min_valuation<'a>(candidates: impl Iterator<Item = (usize, &'a Padic)>) -> Option<usize> {
+    candidates
+        .filter_map(|(i, x)| x.valuation().map(|v| (v, i)))
+        .min()
+        .map(|(_, i)| i)
+}
+
+// subtracts factor * pivot_row from row, starting at column `from`
+fn eliminate(row: &mut [Padic], pivot_row: &[Padic], factor: &Padic, from: usize) {
+    for (x, y) in row.iter_mut().zip(pivot_row).skip(from) {
+        *x = x.sub(&factor.mul(y));
+    }
+}
+
+#[allow(dead_code)]
+impl PadicMatrix {
+    pub fn rows(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.entries.first().map_or(0, |row| row.len())
+    }
+
+    fn precision(&self) -> usize {
+        self.entries
+            .iter()
+            .flatten()
+            .map(|x| x.expanded.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn identity(n: usize, p: i64, precision: usize) -> PadicMatrix {
+        let entries = (0..n)
+            .map(|i| {
+                (0..n)
+                    .map(|j| constant((i == j) as i64, p, precision))
+                    .collect()
+            })
+            .collect();
+        PadicMatrix { p, entries }
+    }
+
+    pub fn mul(&self, other: &PadicMatrix) -> PadicMatrix {
+        assert_eq!(self.cols(), other.rows(), "matrix dimensions do not match");
+        let zero = constant(0, self.p, self.precision().max(other.precision()));
+        let work = self.rows() * self.cols() * other.cols() * zero.expanded.len().pow(2);
+        let entries = map(&self.entries, work, |row| {
+            (0..other.cols())
+                .map(|j| {
+                    row.iter()
+                        .zip(&other.entries)
+                        .fold(zero.clone(), |acc, (x, r)| acc.add(&x.mul(&r[j])))
+                })
+                .collect()
+        });
+        PadicMatrix { p: self.p, entries }
+    }
+
+    // Gaussian elimination to row echelon form, pivoting on the entry of minimal valuation
+    // in each column so that every multiplier lies in Z_p; returns the echelon form, the
+    // pivot columns and the number of row swaps
+    pub fn row_echelon(&self) -> (PadicMatrix, Vec<usize>, usize) {
+        let mut m = self.entries.clone();
+        let mut pivots = Vec::new();
+        let mut swaps = 0;
+        let mut r = 0;
+        for c in 0..self.cols() {
+            if r == self.rows() {
+                break;
+            }
+            let Some(k) = min_valuation((r..self.rows()).map(|i| (i, &m[i][c]))) else {
+                continue;
+            };
+            if k != r {
+                m.swap(k, r);
+                swaps += 1;
+            }
+            let (top, rest) = m.split_at_mut(r + 1);
+            let pivot_row = &top[r];
+            let work = rest.len() * self.cols() * self.precision().pow(2);
+            for_each_mut(rest, work, |row| {
+                let factor = row[c].div(&pivot_row[c]).unwrap();
+                eliminate(row, pivot_row, &factor, c);
+            });
+            pivots.push(c);
+            r += 1;
+        }
+        (
+            PadicMatrix {
+                p: self.p,
+                entries: m,
+            },
+            pivots,
+            swaps,
+        )
+    }
+
+    pub fn rank(&self) -> usize {
+        self.row_echelon().1.len()
+    }
+
+    pub fn determinant(&self) -> Padic {
+        assert_eq!(
+            self.rows(),
+            self.cols(),
+            "determinant of a non-square matrix"
+        );
+        let n = self.rows();
+        let (echelon, pivots, swaps) = self.row_echelon();
+        if pivots.len() < n {
+            return constant(0, self.p, self.precision());
+        }
+        let sign = constant(
+            if swaps % 2 == 0 { 1 } else { -1 },
+            self.p,
+            self.precision(),
+        );
+        (0..n).fold(sign, |acc, i| acc.mul(&echelon.entries[i][i]))
+    }
+
+    // Gauss-Jordan elimination on [A | I], None when A is singular to the known precision
+    pub fn inverse(&self) -> Option<PadicMatrix> {
+        assert_eq!(self.rows(), self.cols(), "inverse of a non-square matrix");
+        let n = self.rows();
+        let id = PadicMatrix::identity(n, self.p, self.precision());
+        let mut m: Vec<Vec<Padic>> = self
+            .entries
+            .iter()
+            .zip(id.entries)
+            .map(|(row, e)| row.iter().cloned().chain(e).collect())
+            .collect();
+        for c in 0..n {
+            let k = min_valuation((c..n).map(|i| (i, &m[i][c])))?;
+            m.swap(k, c);
+            let inv = m[c][c].inverse()?;
+            m[c] = m[c].iter().map(|x| x.mul(&inv)).collect();
+            let pivot_row = m[c].clone();
+            let (above, below) = m.split_at_mut(c);
+            let work = n * 2 * n * self.precision().pow(2);
+            let eliminate_with_pivot = |row: &mut Vec<Padic>| {
+                let factor = row[c].clone();
+                eliminate(row, &pivot_row, &factor, c);
+            };
+            join(
+                work,
+                || for_each_mut(above, work, eliminate_with_pivot),
+                || for_each_mut(&mut below[1..], work, eliminate_with_pivot),
+            );
+        }
+        Some(PadicMatrix {
+            p: self.p,
+            entries: m.into_iter().map(|row| row[n..].to_vec()).collect(),
+        })
+    }
+
+    // solves Ax = b for square A, None when A is singular to the known precision
+    pub fn solve(&self, b: &[Padic]) -> Option<Vec<Padic>> {
+        let column = PadicMatrix {
+            p: self.p,
+            entries: b.iter().map(|x| vec![x.clone()]).collect(),
+        };
+        let x = self.inverse()?.mul(&column);
+        Some(x.entries.into_iter().map(|mut row| row.remove(0)).collect())
+    }
+
+    // Smith normal form over Z_p: the diagonal entries p^k_1 | p^k_2 | ... obtained by full
+    // pivoting on the entry of minimal valuation, zero entries come last
+    pub fn smith_normal_form(&self) -> Vec<Padic> {
+        let precision = self.precision();
+        let mut m = self.entries.clone();
+        let (rows, cols) = (self.rows(), self.cols());
+        let mut divisors = Vec::new();
+        for k in 0..rows.min(cols) {
+            let Some(idx) = min_valuation(
+                (k..rows)
+                    .flat_map(|i| (k..cols).map(move |j| (i, j)))
+                    .enumerate()
+                    .map(|(idx, (i, j))| (idx, &m[i][j])),
+            ) else {
+                break;
+            };
+            let (i, j) = (k + idx / (cols - k), k + idx % (cols - k));
+            m.swap(i, k);
+            m.iter_mut().for_each(|row| row.swap(j, k));
+
+            let pivot_row = m[k].clone();
+            let work = (rows - k) * cols * precision.pow(2);
+            for_each_mut(&mut m[k + 1..], work, |row| {
+                let factor = row[k].div(&pivot_row[k]).unwrap();
+                eliminate(row, &pivot_row, &factor, k);
+            });
+            // once column k is cleared below the pivot, clearing row k by column operations
+            // leaves the remaining submatrix untouched, so those operations are skipped
+            let pivot = pivot_row[k].clone();
+            let v = pivot.valuation().unwrap();
+            divisors.push(if v >= 0 {
+                expand(
+                    &Frac {
+                        numer: self.p.pow(v as u32),
+                        denom: 1,
+                    },
+                    self.p,
+                    precision,
+                )
+            } else {
+                expand(
+                    &Frac {
+                        numer: 1,
+                        denom: self.p.pow(-v as u32),
+                    },
+                    self.p,
+                    precision,
+                )
+            });
+        }
+        while divisors.len() < rows.min(cols) {
+            divisors.push(constant(0, self.p, precision));
+        }
+        divisors
+    }
+
+    // exponents k of the elementary divisors p^k, None for zero divisors
+    pub fn elementary_divisors(&self) -> Vec<Option<i64>> {
+        self.smith_normal_form()
+            .iter()
+            .map(|d| d.valuation())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testing_determinant() {
+        let m = from_integers(&[vec![2, 3], vec![5, 7]], 5, 8);
+        assert_eq!(
+            m.determinant().v,
+            Some(Frac {
+                numer: -1,
+                denom: 1
+            })
+        );
+        assert_eq!(
+            m.determinant().expanded,
+            expand(
+                &Frac {
+                    numer: -1,
+                    denom: 1
+                },
+                5,
+                8
+            )
+            .expanded
+        );
+
+        let singular = from_integers(&[vec![1, 2], vec![2, 4]], 3, 6);
+        assert_eq!(singular.determinant().valuation(), None);
+        assert_eq!(singular.rank(), 1);
+        assert!(singular.inverse().is_none());
+
+        let m = from_integers(&[vec![3, 1, 0], vec![0, 9, 2], vec![1, 0, 3]], 3, 10);
+        // 3(27) - 1(-2) = 83
+        assert_eq!(
+            m.determinant().expanded[..8],
+            expand(
+                &Frac {
+                    numer: 83,
+                    denom: 1
+                },
+                3,
+                8
+            )
+            .expanded[..]
+        );
+    }
+
+    #[test]
+    fn testing_inverse_and_solve() {
+        let m = from_integers(&[vec![5, 1], vec![2, 1]], 5, 10);
+        let inv = m.inverse().unwrap();
+        // det = 3, inverse = 1/3 * [[1, -1], [-2, 5]]
+        assert_eq!(inv.entries[0][0].v, Some(Frac { numer: 1, denom: 3 }));
+        assert_eq!(inv.entries[1][1].v, Some(Frac { numer: 5, denom: 3 }));
+        let id = m.mul(&inv);
+        assert_eq!(id.entries[0][0].normalized().expanded[..5], [1, 0, 0, 0, 0]);
+        assert_eq!(id.entries[0][1].valuation(), None);
+
+        let b = vec![
+            expand(&Frac { numer: 6, denom: 1 }, 5, 10),
+            expand(&Frac { numer: 3, denom: 1 }, 5, 10),
+        ];
+        let x = m.solve(&b).unwrap();
+        assert_eq!(x[0].v, Some(Frac { numer: 1, denom: 1 }));
+        assert_eq!(x[1].v, Some(Frac { numer: 1, denom: 1 }));
+    }
+
+    #[test]
+    fn testing_smith_normal_form() {
+        let m = from_integers(&[vec![2, 4, 4], vec![-6, 6, 12], vec![10, -4, -16]], 2, 12);
+        // over Z the invariant factors are 2, 6, 12
+        assert_eq!(m.elementary_divisors(), vec![Some(1), Some(1), Some(2)]);
+        let m = from_integers(&[vec![2, 4, 4], vec![-6, 6, 12], vec![10, -4, -16]], 3, 12);
+        assert_eq!(m.elementary_divisors(), vec![Some(0), Some(1), Some(1)]);
+
+        let m = from_integers(&[vec![7, 14], vec![49, 98]], 7, 8);
+        assert_eq!(m.elementary_divisors(), vec![Some(1), None]);
+    }
+}
@@ -1,42 +1,69 @@
+use crate::combinatorics::*;
+use crate::dispatch::*;
 use crate::frac::*;
 use rayon::prelude::*;
+use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Padic {
-    pub v: Frac,
+    // the exact rational the digits expand, None once an operation leaves i64 or the value is
+    // not rational
+    pub v: Option<Frac>,
     pub p: i64,
     pub expanded: Vec<i64>,
+    // power of p carried by expanded[0], negative when v has p in its denominator
+    pub offset: i64,
 }
 
 #[allow(dead_code)]
 pub fn expand(f: &Frac, p: i64, precision: usize) -> Padic {
+    let mut offset = 0;
+    let mut b = f.denom;
+    while f.numer != 0 && b % p == 0 {
+        b /= p;
+        offset -= 1;
+    }
     let mut a = f.numer;
-    let b = f.denom;
     let mut expanded = Vec::new();
 
     for _ in 0..precision {
-        let a2 = (0..p)
-            .into_par_iter()
-            .map(|k| ((a - k * b) as f64) / p as f64)
-            .collect::<Vec<f64>>();
-        // for all k, a2 = (a/b - k)*(b/p)
-        let a3 = (0..p)
-            .into_par_iter()
-            .find_first(|&k| a2[k as usize].fract() == 0.0)
-            .unwrap() as i64;
-        // for all k, a3 = (a2/b - k)*(b/p)
+        // the digit k makes (a/b - k)*(b/p) = (a - k*b)/p integral; only a search over a large
+        // p is worth splitting across threads
+        let integral = |&k: &i64| (a - k * b) % p == 0;
+        let a3 = if parallel(p as usize) {
+            (0..p).into_par_iter().find_first(integral)
+        } else {
+            (0..p).find(integral)
+        }
+        .unwrap();
         expanded.push(a3);
         a = (a - a3 * b) / p;
     }
 
-    Padic { v: *f, p, expanded }
+    Padic {
+        v: Some(*f),
+        p,
+        expanded,
+        offset,
+    }
+}
+
+impl fmt::Display for Padic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut digits: Vec<String> = map(&self.expanded, self.expanded.len(), |&k| k.to_string());
+        if self.offset < 0 {
+            let point = (-self.offset as usize).min(digits.len());
+            digits.insert(point, ".".to_string());
+        }
+        digits.extend((0..self.offset.max(0)).map(|_| "0".to_string()));
+        digits.reverse();
+        write!(f, "...{}", digits.join(""))
+    }
 }
 
 #[allow(dead_code)]
 pub fn print_p_adic(padic: &Padic) {
-    let reversed: Vec<i64> = padic.expanded.iter().cloned().rev().collect();
-    let str: Vec<String> = reversed.par_iter().map(|&k| k.to_string()).collect();
-    println!("...{}", str.join(""));
+    println!("{}", padic);
 }
 
 #[allow(dead_code)]
@@ -49,7 +76,7 @@ fn back_converter(n: i64) -> Vec<i64> {
     let num = n.to_string().chars().rev().collect::<String>();
     let num_chars: Vec<char> = num.chars().collect();
     let expanded: Vec<i64> = num_chars
-        .par_iter()
+        .iter()
         .filter_map(|c| c.to_digit(10).map(|d| d as i64))
         .collect();
     expanded
@@ -57,7 +84,8 @@ fn back_converter(n: i64) -> Vec<i64> {
 
 #[allow(dead_code)]
 pub fn add_p_adic(p1: &Padic, p2: &Padic) -> Padic {
-    let (i1, i2) = rayon::join(
+    let (i1, i2) = join(
+        p1.expanded.len() + p2.expanded.len(),
         || converter(p1.expanded.clone()),
         || converter(p2.expanded.clone()),
     );
@@ -65,15 +93,381 @@ pub fn add_p_adic(p1: &Padic, p2: &Padic) -> Padic {
     let expanded: Vec<i64> = back_converter(i1 + i2);
 
     Padic {
-        v: Frac::add(&p1.v, &p2.v),
+        v: p1.v.zip(p2.v).and_then(|(a, b)| a.checked_add(&b)),
         p: p1.p,
         expanded,
+        offset: p1.offset,
     }
 }
 
+// the digits stand in when there is no exact rational to print
 #[allow(dead_code)]
 pub fn print_as_frac(p: &Padic) {
-    println!("{}", p.v);
+    match p.v {
+        Some(v) => println!("{}", v),
+        None => println!("{}", p),
+    }
+}
+
+#[allow(dead_code)]
+pub fn mod_pow(base: i64, exp: i64, m: i64) -> i64 {
+    let m = m as i128;
+    let mut result: i128 = 1 % m;
+    let mut b = (base as i128).rem_euclid(m);
+    let mut e = exp;
+    while e > 0 {
+        if e & 1 == 1 {
+            result = result * b % m;
+        }
+        b = b * b % m;
+        e >>= 1;
+    }
+    result as i64
+}
+
+#[allow(dead_code)]
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    let (mut r0, mut r1) = (a.rem_euclid(m) as i128, m as i128);
+    let (mut s0, mut s1) = (1i128, 0i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+    if r0 != 1 {
+        return None;
+    }
+    Some(s0.rem_euclid(m as i128) as i64)
+}
+
+#[allow(dead_code)]
+fn add_digits(a: &[i64], b: &[i64], p: i64, n: usize) -> Vec<i64> {
+    let mut carry = 0;
+    (0..n)
+        .map(|i| {
+            let s = a.get(i).unwrap_or(&0) + b.get(i).unwrap_or(&0) + carry;
+            carry = s / p;
+            s % p
+        })
+        .collect()
+}
+
+#[allow(dead_code)]
+fn neg_digits(a: &[i64], p: i64) -> Vec<i64> {
+    // -x = (complement of x) + 1
+    let complement: Vec<i64> = a.iter().map(|&d| p - 1 - d).collect();
+    add_digits(&complement, &[1], p, a.len())
+}
+
+#[allow(dead_code)]
+fn mul_digits(a: &[i64], b: &[i64], p: i64, n: usize) -> Vec<i64> {
+    let mut carry: i128 = 0;
+    (0..n)
+        .map(|k| {
+            let mut acc = carry;
+            for i in 0..=k {
+                if let (Some(&x), Some(&y)) = (a.get(i), b.get(k - i)) {
+                    acc += x as i128 * y as i128;
+                }
+            }
+            carry = acc / p as i128;
+            (acc % p as i128) as i64
+        })
+        .collect()
+}
+
+// quotient a/b to n digits, None unless b[0] is invertible mod p, which for a composite base
+// such as 10 is more than being nonzero
+#[allow(dead_code)]
+fn div_digits(a: &[i64], b: &[i64], p: i64, n: usize) -> Option<Vec<i64>> {
+    let inv = mod_inverse(b[0], p)?;
+    let mut r: Vec<i64> = (0..n).map(|i| *a.get(i).unwrap_or(&0)).collect();
+    let mut q = Vec::with_capacity(n);
+    for i in 0..n {
+        let d = (r[i] as i128 * inv as i128 % p as i128) as i64;
+        q.push(d);
+        // r -= d * b * p^i
+        let mut borrow: i128 = 0;
+        for (j, rj) in r.iter_mut().enumerate().skip(i) {
+            let t = *rj as i128 - d as i128 * *b.get(j - i).unwrap_or(&0) as i128 + borrow;
+            *rj = t.rem_euclid(p as i128) as i64;
+            borrow = t.div_euclid(p as i128);
+        }
+    }
+    Some(q)
+}
+
+#[allow(dead_code)]
+impl Padic {
+    pub fn with_digits(p: i64, expanded: Vec<i64>, offset: i64, v: Option<Frac>) -> Padic {
+        Padic {
+            v,
+            p,
+            expanded,
+            offset,
+        }
+    }
+
+    // absolute precision: the value is known modulo p^precision
+    pub fn precision(&self) -> i64 {
+        self.offset + self.expanded.len() as i64
+    }
+
+    // None when every known digit is zero
+    pub fn valuation(&self) -> Option<i64> {
+        self.expanded
+            .iter()
+            .position(|&d| d != 0)
+            .map(|i| self.offset + i as i64)
+    }
+
+    // moves leading zero digits into the offset
+    pub fn normalized(&self) -> Padic {
+        let zeros = self
+            .expanded
+            .iter()
+            .position(|&d| d != 0)
+            .unwrap_or(self.expanded.len());
+        Padic {
+            v: self.v,
+            p: self.p,
+            expanded: self.expanded[zeros..].to_vec(),
+            offset: self.offset + zeros as i64,
+        }
+    }
+
+    pub fn add(&self, other: &Padic) -> Padic {
+        assert_eq!(self.p, other.p, "p-adic numbers with different primes");
+        let offset = self.offset.min(other.offset);
+        let n = (self.precision().min(other.precision()) - offset).max(0) as usize;
+        let shifted = |x: &Padic| -> Vec<i64> {
+            let mut digits = vec![0; (x.offset - offset) as usize];
+            digits.extend_from_slice(&x.expanded);
+            digits
+        };
+        let (a, b) = join(n, || shifted(self), || shifted(other));
+        Padic::with_digits(
+            self.p,
+            add_digits(&a, &b, self.p, n),
+            offset,
+            self.v.zip(other.v).and_then(|(a, b)| a.checked_add(&b)),
+        )
+    }
+
+    pub fn neg(&self) -> Padic {
+        Padic::with_digits(
+            self.p,
+            neg_digits(&self.expanded, self.p),
+            self.offset,
+            self.v.map(|v| v.neg()),
+        )
+    }
+
+    pub fn sub(&self, other: &Padic) -> Padic {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Padic) -> Padic {
+        assert_eq!(self.p, other.p, "p-adic numbers with different primes");
+        let (x, y) = (self.normalized(), other.normalized());
+        let n = x.expanded.len().min(y.expanded.len());
+        Padic::with_digits(
+            self.p,
+            mul_digits(&x.expanded, &y.expanded, self.p, n),
+            x.offset + y.offset,
+            self.v.zip(other.v).and_then(|(a, b)| a.checked_mul(&b)),
+        )
+    }
+
+    // None when other is zero to its known precision, or when its leading digit is not
+    // invertible in a composite base
+    pub fn div(&self, other: &Padic) -> Option<Padic> {
+        assert_eq!(self.p, other.p, "p-adic numbers with different primes");
+        let (x, y) = (self.normalized(), other.normalized());
+        if y.expanded.is_empty() {
+            return None;
+        }
+        let n = x.expanded.len().min(y.expanded.len());
+        Some(Padic::with_digits(
+            self.p,
+            div_digits(&x.expanded, &y.expanded, self.p, n)?,
+            x.offset - y.offset,
+            self.v.zip(other.v).and_then(|(a, b)| a.checked_div(&b)),
+        ))
+    }
+
+    pub fn inverse(&self) -> Option<Padic> {
+        let one = expand(&Frac { numer: 1, denom: 1 }, self.p, self.expanded.len());
+        one.div(self)
+    }
+
+    fn resized(&self, n: usize) -> Padic {
+        let mut expanded = self.expanded.clone();
+        expanded.resize(n, 0);
+        Padic {
+            v: self.v,
+            p: self.p,
+            expanded,
+            offset: self.offset,
+        }
+    }
+
+    // repeated squaring, negative exponents go through the inverse
+    pub fn pow(&self, n: i64) -> Padic {
+        let base = if n < 0 {
+            self.inverse()
+                .expect("negative power of a non-invertible element")
+        } else {
+            self.clone()
+        };
+        let one = expand(&Frac { numer: 1, denom: 1 }, self.p, self.expanded.len());
+        let mut result = one;
+        let mut square = base;
+        let mut e = n.unsigned_abs();
+        while e > 0 {
+            if e & 1 == 1 {
+                result = result.mul(&square);
+            }
+            e >>= 1;
+            if e > 0 {
+                square = square.mul(&square);
+            }
+        }
+        result
+    }
+
+    // the (p-1)-th root of unity congruent to a unit, lim u^(p^n)
+    pub fn teichmuller(&self) -> Padic {
+        let u = self.normalized();
+        assert_eq!(u.offset, 0, "Teichmuller lift of a non-unit");
+        (0..u.expanded.len()).fold(u.clone(), |x, _| x.pow(self.p))
+    }
+
+    // u^a for a unit u and a in Z_p. On 1 + pZ_p this is the binomial series
+    // (1 + x)^a = sum C(a, n) x^n for any a. Another unit is ωv with ω its Teichmuller root of
+    // unity and v in 1 + pZ_p, and ω^a is only meaningful for an exact rational a = r/s, as the
+    // root of unity ζ with ζ^s = ω^r when there is exactly one. None when u is not a unit or a
+    // is not in Z_p, and for u outside 1 + pZ_p when a has no exact rational value or ω^r has
+    // no s-th root of unity or several, e.g. 6^(1/3) in Q_7
+    pub fn pow_padic(&self, exponent: &Padic) -> Option<Padic> {
+        assert_eq!(self.p, exponent.p, "p-adic numbers with different primes");
+        let p = self.p;
+        let u = self.normalized();
+        if u.offset != 0 || u.expanded.is_empty() || exponent.offset < 0 {
+            return None;
+        }
+
+        let u0 = u.expanded[0];
+        let (one_unit, root) = if u0 == 1 {
+            (u.clone(), None)
+        } else {
+            let a = exponent.v?;
+            let a = Frac::reduce(a.numer, a.denom);
+            let target = if a.numer >= 0 {
+                mod_pow(u0, a.numer, p)
+            } else {
+                mod_pow(mod_inverse(u0, p)?, -a.numer, p)
+            };
+            let roots: Vec<i64> = (1..p)
+                .filter(|&t| mod_pow(t, a.denom, p) == target)
+                .collect();
+            let [t] = roots[..] else {
+                return None;
+            };
+            (u.div(&u.teichmuller())?, Some(t))
+        };
+        let x = one_unit.sub(&expand(&Frac { numer: 1, denom: 1 }, p, u.expanded.len()));
+        // u^a is known modulo p^(prec(u)) and p^(prec(a) + v(x))
+        let m = x.valuation().unwrap_or(x.precision()).max(1);
+        let n = (u.expanded.len() as i64).min(exponent.precision() + m) as usize;
+
+        // C(a, n) lies in Z_p, so the terms after n / v(x) vanish mod p^n, and dividing by
+        // k! loses at most v_p(k!) digits
+        let terms = (n as i64 + m - 1) / m;
+        let work = n + valuation_of_factorial(terms, p) as usize;
+        let (a, x) = (exponent.resized(work), x.resized(work));
+        let one = expand(&Frac { numer: 1, denom: 1 }, p, work);
+        let mut term = one.clone();
+        let mut sum = one;
+        for k in 1..=terms {
+            // C(a, k) x^k = C(a, k - 1) x^(k - 1) * (a - k + 1) x / k
+            let factor = a.sub(&expand(
+                &Frac {
+                    numer: k - 1,
+                    denom: 1,
+                },
+                p,
+                work,
+            ));
+            let k = expand(&Frac { numer: k, denom: 1 }, p, work);
+            term = term.mul(&factor).mul(&x).div(&k)?;
+            sum = sum.add(&term);
+        }
+        let result = match root {
+            Some(t) => expand(&Frac { numer: t, denom: 1 }, p, n)
+                .teichmuller()
+                .mul(&sum),
+            None => sum,
+        }
+        .normalized();
+        Some(Padic::with_digits(
+            p,
+            result.expanded[..n.min(result.expanded.len())].to_vec(),
+            result.offset,
+            None,
+        ))
+    }
+}
+
+// Morita's Γ_p on Z_p: Γ_p(n) = (-1)^n times the product of the j < n prime to p, extended by
+// continuity from the integer the known digits of x spell out. The units below p^N multiply to
+// -1 mod p^N (to 1 when p = 2 and N >= 3), so Γ_p(x) is known to as many digits as x is,
+// except for p = 2 and N = 2 where one digit is lost. The product over up to p^N factors
+// goes through factorial_unit in blocks of p^k, about p N^3 operations, and N is capped
+// where p^N reaches 2^62.
+#[allow(dead_code)]
+pub fn padic_gamma(x: &Padic) -> Padic {
+    let p = x.p;
+    assert!(
+        x.expanded
+            .iter()
+            .enumerate()
+            .all(|(i, &d)| d == 0 || x.offset + i as i64 >= 0),
+        "Γ_p is only defined on Z_p"
+    );
+    let mut n = 0i64;
+    let mut modulus = 1i64;
+    let mut precision = 0;
+    while (precision as i64) < x.precision() {
+        let Some(next) = modulus.checked_mul(p).filter(|m| *m < 1 << 62) else {
+            break;
+        };
+        let i = precision as i64 - x.offset;
+        if i >= 0 {
+            n += x.expanded[i as usize] * modulus;
+        }
+        modulus = next;
+        precision += 1;
+    }
+    let unit = factorial_unit(n - 1, p, modulus);
+    let gamma = if n % 2 == 0 {
+        unit
+    } else {
+        (modulus - unit) % modulus
+    };
+    if p == 2 && precision == 2 {
+        precision = 1;
+    }
+    let digits = expand(
+        &Frac {
+            numer: gamma,
+            denom: 1,
+        },
+        p,
+        precision,
+    )
+    .expanded;
+    Padic::with_digits(p, digits, 0, None)
 }
 
 #[cfg(test)]
@@ -100,20 +494,20 @@ mod tests {
         assert_eq!(padic.expanded, vec![1, 5, 5, 0, 3, 5, 0, 3, 5, 0]);
         assert_eq!(
             padic.v,
-            Frac {
+            Some(Frac {
                 numer: 128,
                 denom: 9
-            }
+            })
         );
 
         let total = add_p_adic(&padic, &padic);
         assert_eq!(total.expanded, vec![2, 0, 1, 1, 6, 0, 1, 6, 0, 1]);
         assert_eq!(
             total.v,
-            Frac {
+            Some(Frac {
                 numer: 256,
                 denom: 9
-            }
+            })
         );
 
         let third = Frac { numer: 1, denom: 3 };
@@ -125,5 +519,306 @@ mod tests {
             denom: 1,
         };
         assert_eq!(expand(&neg, 10, 72).expanded, vec![9; 72]);
+
+        // 2 * 3^37 + 1 is beyond the 53 bits in which floats hold integers exactly
+        let big = Frac {
+            numer: 2 * 3i64.pow(37) + 1,
+            denom: 1,
+        };
+        let mut digits = vec![0; 39];
+        (digits[0], digits[37]) = (1, 2);
+        assert_eq!(expand(&big, 3, 39).expanded, digits);
+    }
+
+    #[test]
+    fn testing_field_operations() {
+        let p = 7;
+        let x = expand(&Frac { numer: 2, denom: 5 }, p, 10);
+        let y = expand(
+            &Frac {
+                numer: -3,
+                denom: 4,
+            },
+            p,
+            10,
+        );
+        assert_eq!(
+            x.add(&y).expanded,
+            expand(
+                &Frac {
+                    numer: -7,
+                    denom: 20
+                },
+                p,
+                10
+            )
+            .expanded
+        );
+        assert_eq!(
+            x.sub(&y).expanded,
+            expand(
+                &Frac {
+                    numer: 23,
+                    denom: 20
+                },
+                p,
+                10
+            )
+            .expanded
+        );
+        assert_eq!(
+            x.mul(&y).expanded,
+            expand(
+                &Frac {
+                    numer: -3,
+                    denom: 10
+                },
+                p,
+                10
+            )
+            .expanded
+        );
+        assert_eq!(
+            x.div(&y).unwrap().expanded,
+            expand(
+                &Frac {
+                    numer: -8,
+                    denom: 15
+                },
+                p,
+                10
+            )
+            .expanded
+        );
+        assert_eq!(x.neg().add(&x).valuation(), None);
+
+        let seventh = expand(&Frac { numer: 1, denom: 7 }, p, 5);
+        assert_eq!(seventh.offset, -1);
+        assert_eq!(seventh.valuation(), Some(-1));
+        let forty_nine = expand(
+            &Frac {
+                numer: 49,
+                denom: 1,
+            },
+            p,
+            5,
+        );
+        assert_eq!(forty_nine.valuation(), Some(2));
+        let inv = forty_nine.inverse().unwrap();
+        assert_eq!(inv.valuation(), Some(-2));
+        assert_eq!(
+            inv.v,
+            Some(Frac {
+                numer: 1,
+                denom: 49
+            })
+        );
+        assert_eq!(
+            format!("{}", expand(&Frac { numer: 8, denom: 7 }, p, 3)),
+            "...01.1"
+        );
+
+        // values that leave i64 keep their digits but lose the rational, sign included
+        let minus_third = expand(
+            &Frac {
+                numer: -1,
+                denom: 3,
+            },
+            5,
+            10,
+        );
+        let large = |n: i64| expand(&Frac { numer: n, denom: 1 }, 5, 10);
+        let product = minus_third
+            .mul(&large(3_000_000_000_000))
+            .mul(&large(3_000_000_000));
+        assert_eq!(product.v, None);
+        assert_eq!(
+            product.expanded,
+            large(-1_000_000_000_000)
+                .mul(&large(3_000_000_000))
+                .expanded
+        );
+        assert_eq!(product.add(&product).v, None);
+
+        // in base 10 a nonzero leading digit need not be invertible
+        let ten_adic = |n: i64, d: i64| expand(&Frac { numer: n, denom: d }, 10, 5);
+        assert!(ten_adic(1, 3).div(&ten_adic(2, 1)).is_none());
+        assert_eq!(
+            ten_adic(1, 3).div(&ten_adic(3, 1)).unwrap().expanded,
+            ten_adic(1, 9).expanded
+        );
+    }
+
+    #[test]
+    fn testing_powers() {
+        let x = expand(&Frac { numer: 2, denom: 3 }, 5, 10);
+        assert_eq!(
+            x.pow(5).v,
+            Some(Frac {
+                numer: 32,
+                denom: 243
+            })
+        );
+        assert_eq!(
+            x.pow(5).expanded,
+            expand(
+                &Frac {
+                    numer: 32,
+                    denom: 243
+                },
+                5,
+                10
+            )
+            .expanded
+        );
+        assert_eq!(x.pow(-2).v, Some(Frac { numer: 9, denom: 4 }));
+        assert_eq!(x.pow(0).v, Some(Frac { numer: 1, denom: 1 }));
+
+        let five = expand(&Frac { numer: 5, denom: 1 }, 5, 10);
+        assert_eq!(five.pow(3).valuation(), Some(3));
+        assert_eq!(five.pow(-2).valuation(), Some(-2));
+
+        // powers that leave i64 keep their digits but lose the rational
+        let big = expand(&Frac { numer: 1, denom: 3 }, 7, 30).pow(45);
+        assert_eq!(big.v, None);
+        assert_eq!(
+            big.expanded,
+            expand(&Frac { numer: 1, denom: 3 }, 7, 30)
+                .pow(15)
+                .pow(3)
+                .expanded
+        );
+    }
+
+    #[test]
+    fn testing_padic_exponents() {
+        let third = expand(&Frac { numer: 1, denom: 3 }, 7, 10);
+        // 8 = 1 mod 7, the binomial series picks the cube root 2ω = 1 mod 7
+        let eight = expand(&Frac { numer: 8, denom: 1 }, 7, 10);
+        let root = eight.pow_padic(&third).unwrap();
+        assert_eq!(root.expanded[0], 1);
+        assert_eq!(root.pow(3).expanded, eight.expanded);
+        // 2 is not a cube mod 7
+        let two = expand(&Frac { numer: 2, denom: 1 }, 7, 10);
+        assert!(two.pow_padic(&third).is_none());
+
+        // cubing is a bijection on the units of Z_5, so 2^(1/3) exists in Q_5
+        let third = expand(&Frac { numer: 1, denom: 3 }, 5, 12);
+        let two = expand(&Frac { numer: 2, denom: 1 }, 5, 12);
+        let root = two.pow_padic(&third).unwrap();
+        assert_eq!(root.pow(3).expanded[..10], two.expanded[..10]);
+        let eight = expand(&Frac { numer: 8, denom: 1 }, 5, 12);
+        assert_eq!(
+            eight.pow_padic(&third).unwrap().expanded[..10],
+            two.expanded[..10]
+        );
+
+        let omega = two.teichmuller();
+        assert_eq!(omega.pow(4).expanded[..10], [1, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(omega.expanded[0], 2);
+
+        let minus_one = expand(
+            &Frac {
+                numer: -1,
+                denom: 1,
+            },
+            5,
+            12,
+        );
+        let six = expand(&Frac { numer: 6, denom: 1 }, 5, 12);
+        assert_eq!(
+            six.pow_padic(&minus_one).unwrap().expanded[..10],
+            expand(&Frac { numer: 1, denom: 6 }, 5, 10).expanded[..]
+        );
+        assert!(five_adic_non_unit().pow_padic(&third).is_none());
+
+        // a truly p-adic exponent, known only by its digits, applies to 1 + 5Z_5 alone
+        let digits_only = Padic::with_digits(5, third.expanded.clone(), 0, None);
+        assert_eq!(
+            six.pow_padic(&digits_only).unwrap().expanded,
+            six.pow_padic(&third).unwrap().expanded
+        );
+        assert!(two.pow_padic(&digits_only).is_none());
+        // 3, 5 and 6 all cube to 6 mod 7, so no cube root of 6 is singled out
+        let third = expand(&Frac { numer: 1, denom: 3 }, 7, 10);
+        let six = expand(&Frac { numer: 6, denom: 1 }, 7, 10);
+        assert!(six.pow_padic(&third).is_none());
+    }
+
+    fn five_adic_non_unit() -> Padic {
+        expand(
+            &Frac {
+                numer: 10,
+                denom: 1,
+            },
+            5,
+            12,
+        )
+    }
+
+    #[test]
+    fn testing_padic_gamma() {
+        let int = |n: i64, p: i64| expand(&Frac { numer: n, denom: 1 }, p, 6);
+        // Γ_5(3) = -2, Γ_5(6) = 4! = 24, Γ_p(0) = 1, Γ_p(1) = -1
+        assert_eq!(padic_gamma(&int(3, 5)).expanded, int(-2, 5).expanded);
+        assert_eq!(padic_gamma(&int(6, 5)).expanded, int(24, 5).expanded);
+        assert_eq!(padic_gamma(&int(0, 7)).expanded, int(1, 7).expanded);
+        assert_eq!(padic_gamma(&int(1, 7)).expanded, int(-1, 7).expanded);
+
+        // reflection: Γ_p(x)Γ_p(1 - x) = (-1)^R with R in 1..=p and R = x mod p
+        let half = expand(&Frac { numer: 1, denom: 2 }, 5, 6);
+        let g = padic_gamma(&half);
+        assert_eq!(g.precision(), 6);
+        assert_eq!(g.mul(&g).expanded, int(-1, 5).expanded);
+        let half = expand(&Frac { numer: 1, denom: 2 }, 7, 5);
+        let g = padic_gamma(&half);
+        assert_eq!(
+            g.mul(&g).expanded,
+            expand(&Frac { numer: 1, denom: 1 }, 7, 5).expanded
+        );
+        let x = expand(&Frac { numer: 2, denom: 3 }, 5, 6);
+        let y = expand(&Frac { numer: 1, denom: 3 }, 5, 6);
+        // 2/3 = 4 mod 5
+        assert_eq!(
+            padic_gamma(&x).mul(&padic_gamma(&y)).expanded,
+            int(1, 5).expanded
+        );
+
+        // Γ_7(-1/2)Γ_7(3/2) = (-1)^3 with -1/2 = 3 mod 7, to 20 digits
+        let x = expand(
+            &Frac {
+                numer: -1,
+                denom: 2,
+            },
+            7,
+            20,
+        );
+        let y = expand(&Frac { numer: 3, denom: 2 }, 7, 20);
+        let g = padic_gamma(&x);
+        assert_eq!(g.precision(), 20);
+        assert_eq!(
+            g.mul(&padic_gamma(&y)).expanded,
+            expand(
+                &Frac {
+                    numer: -1,
+                    denom: 1
+                },
+                7,
+                20
+            )
+            .expanded
+        );
+
+        // continuity: 3 and 3 + 5^4 agree to 4 digits
+        let a = padic_gamma(&int(3, 5));
+        let b = padic_gamma(&int(3 + 625, 5));
+        assert_eq!(a.expanded[..4], b.expanded[..4]);
+    }
+
+    #[test]
+    fn testing_mod_helpers() {
+        assert_eq!(mod_pow(3, 4, 7), 4);
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(7, 49), None);
     }
 }
@@ -1,5 +1,6 @@
+use crate::dispatch::*;
+use crate::dyadic::*;
 use crate::surreal::*;
-use rayon::prelude::*;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PStar {
@@ -27,8 +28,8 @@ pub fn star(n: i32) -> Surreal {
         }
     }
     let star = Surreal {
-        l: Some(vec![SurrealValue::Float(0.0)]),
-        r: Some(vec![SurrealValue::Float(0.0)]),
+        l: Some(vec![SurrealValue::Dyadic(Dyadic::integer(0))]),
+        r: Some(vec![SurrealValue::Dyadic(Dyadic::integer(0))]),
     };
     if n == 1 {
         star
@@ -81,24 +82,12 @@ pub fn pretty_astar(n1: i32, n2: i32) -> PStar {
 
 #[allow(dead_code)]
 pub fn print_pstar(star: &PStar) {
-    let l_string: String = star
-        .l
-        .par_iter()
-        .map(|v| match v {
-            StarValue::String(s) => s.clone(),
-            StarValue::Integer(i) => i.to_string(),
-        })
-        .collect::<Vec<String>>()
-        .join(", ");
-    let r_string: String = star
-        .r
-        .par_iter()
-        .map(|v| match v {
-            StarValue::String(s) => s.clone(),
-            StarValue::Integer(i) => i.to_string(),
-        })
-        .collect::<Vec<String>>()
-        .join(", ");
+    let to_string = |v: &StarValue| match v {
+        StarValue::String(s) => s.clone(),
+        StarValue::Integer(i) => i.to_string(),
+    };
+    let l_string: String = map(&star.l, star.l.len(), to_string).join(", ");
+    let r_string: String = map(&star.r, star.r.len(), to_string).join(", ");
     println!("{{ {} | {} }}", l_string, r_string);
 }
 
@@ -113,18 +102,19 @@ fn star_to_surreal(value: &StarValue) -> SurrealValue {
                 SurrealValue::Surreal(star(n))
             }
         }
-        StarValue::Integer(i) => SurrealValue::Float(*i as f64),
+        StarValue::Integer(i) => SurrealValue::Dyadic(Dyadic::integer(*i as i64)),
     }
 }
 
 #[allow(dead_code)]
 fn map_to_surreal(values: &[StarValue]) -> Vec<SurrealValue> {
-    values.par_iter().map(star_to_surreal).collect()
+    map(values, values.len(), star_to_surreal)
 }
 
 #[allow(dead_code)]
 pub fn expand_pstar(star: PStar) -> Surreal {
-    let (l, r): (Option<Vec<SurrealValue>>, Option<Vec<SurrealValue>>) = rayon::join(
+    let (l, r): (Option<Vec<SurrealValue>>, Option<Vec<SurrealValue>>) = join(
+        star.l.len() + star.r.len(),
         || {
             if star.l.is_empty() {
                 None
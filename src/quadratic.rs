@@ -0,0 +1,279 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ramification {
+    Unramified,
+    Ramified,
+}
+
+// a + b√d in Q_p(√d)
+#[derive(Debug, Clone)]
+pub struct Quadratic {
+    pub a: Padic,
+    pub b: Padic,
+    pub d: i64,
+    pub ramification: Ramification,
+}
+
+// returns None when d is zero or already a square in Q_p, i.e. Q_p(√d) is not a field
+#[allow(dead_code)]
+pub fn ramification(d: i64, p: i64) -> Option<Ramification> {
+    if d == 0 {
+        return None;
+    }
+    let mut k = 0;
+    let mut u = d;
+    while u % p == 0 {
+        u /= p;
+        k += 1;
+    }
+    if k % 2 == 1 {
+        return Some(Ramification::Ramified);
+    }
+    if p == 2 {
+        match u.rem_euclid(8) {
+            1 => None,
+            5 => Some(Ramification::Unramified),
+            _ => Some(Ramification::Ramified),
+        }
+    } else if mod_pow(u, (p - 1) / 2, p) == 1 {
+        None
+    } else {
+        Some(Ramification::Unramified)
+    }
+}
+
+// mixing primes or extensions panics, as it does for Padic; None is kept for d that give no
+// field
+#[allow(dead_code)]
+impl Quadratic {
+    pub fn new(a: &Padic, b: &Padic, d: i64) -> Option<Quadratic> {
+        assert_eq!(a.p, b.p, "p-adic numbers with different primes");
+        Some(Quadratic {
+            a: a.clone(),
+            b: b.clone(),
+            d,
+            ramification: ramification(d, a.p)?,
+        })
+    }
+
+    pub fn from_frac(a: &Frac, b: &Frac, d: i64, p: i64, precision: usize) -> Option<Quadratic> {
+        let (x, y) = join(
+            precision * p as usize,
+            || expand(a, p, precision),
+            || expand(b, p, precision),
+        );
+        Quadratic::new(&x, &y, d)
+    }
+
+    // √d itself, e.g. √7 in Q_7(√7)
+    pub fn sqrt(d: i64, p: i64, precision: usize) -> Option<Quadratic> {
+        Quadratic::from_frac(
+            &Frac { numer: 0, denom: 1 },
+            &Frac { numer: 1, denom: 1 },
+            d,
+            p,
+            precision,
+        )
+    }
+
+    fn with(&self, a: Padic, b: Padic) -> Quadratic {
+        Quadratic {
+            a,
+            b,
+            d: self.d,
+            ramification: self.ramification,
+        }
+    }
+
+    fn digits(&self) -> usize {
+        self.a.expanded.len().max(self.b.expanded.len())
+    }
+
+    fn d_padic(&self) -> Padic {
+        let precision = self.digits();
+        expand(
+            &Frac {
+                numer: self.d,
+                denom: 1,
+            },
+            self.a.p,
+            precision,
+        )
+    }
+
+    pub fn add(&self, other: &Quadratic) -> Quadratic {
+        assert_eq!(self.d, other.d, "elements of different extensions");
+        let (a, b) = join(
+            self.digits(),
+            || self.a.add(&other.a),
+            || self.b.add(&other.b),
+        );
+        self.with(a, b)
+    }
+
+    pub fn neg(&self) -> Quadratic {
+        self.with(self.a.neg(), self.b.neg())
+    }
+
+    pub fn sub(&self, other: &Quadratic) -> Quadratic {
+        self.add(&other.neg())
+    }
+
+    pub fn mul(&self, other: &Quadratic) -> Quadratic {
+        // (a1 + b1√d)(a2 + b2√d) = (a1a2 + d b1b2) + (a1b2 + a2b1)√d
+        assert_eq!(self.d, other.d, "elements of different extensions");
+        let d = self.d_padic();
+        let (a, b) = join(
+            3 * self.digits().pow(2),
+            || self.a.mul(&other.a).add(&d.mul(&self.b.mul(&other.b))),
+            || self.a.mul(&other.b).add(&other.a.mul(&self.b)),
+        );
+        self.with(a, b)
+    }
+
+    pub fn conjugate(&self) -> Quadratic {
+        self.with(self.a.clone(), self.b.neg())
+    }
+
+    pub fn trace(&self) -> Padic {
+        self.a.add(&self.a)
+    }
+
+    pub fn norm(&self) -> Padic {
+        // a^2 - d b^2
+        let d = self.d_padic();
+        self.a.mul(&self.a).sub(&d.mul(&self.b.mul(&self.b)))
+    }
+
+    // valuation normalized so that a uniformizer of Q_p(√d) has valuation 1
+    pub fn valuation(&self) -> Option<i64> {
+        let v = self.norm().valuation()?;
+        match self.ramification {
+            Ramification::Unramified => Some(v / 2),
+            Ramification::Ramified => Some(v),
+        }
+    }
+
+    // None when the element is zero to the known precision
+    pub fn inverse(&self) -> Option<Quadratic> {
+        let n = self.norm();
+        let conj = self.conjugate();
+        let (a, b) = join(self.digits().pow(2), || conj.a.div(&n), || conj.b.div(&n));
+        Some(self.with(a?, b?))
+    }
+
+    pub fn div(&self, other: &Quadratic) -> Option<Quadratic> {
+        Some(self.mul(&other.inverse()?))
+    }
+}
+
+impl fmt::Display for Quadratic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}) + ({})√{}", self.a, self.b, self.d)
+    }
+}
+
+#[allow(dead_code)]
+pub fn print_quadratic(x: &Quadratic) {
+    println!("{}", x);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testing_ramification() {
+        assert_eq!(ramification(7, 7), Some(Ramification::Ramified));
+        assert_eq!(ramification(3, 7), Some(Ramification::Unramified));
+        assert_eq!(ramification(2, 7), None);
+        assert_eq!(ramification(49, 7), None);
+        assert_eq!(ramification(5, 2), Some(Ramification::Unramified));
+        assert_eq!(ramification(3, 2), Some(Ramification::Ramified));
+        assert_eq!(ramification(17, 2), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "different primes")]
+    fn testing_mixed_primes() {
+        let x = expand(&Frac { numer: 1, denom: 1 }, 7, 4);
+        let y = expand(&Frac { numer: 1, denom: 1 }, 5, 4);
+        Quadratic::new(&x, &y, 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "different extensions")]
+    fn testing_mixed_extensions() {
+        let x = Quadratic::sqrt(3, 7, 4).unwrap();
+        let y = Quadratic::sqrt(5, 7, 4).unwrap();
+        x.add(&y);
+    }
+
+    #[test]
+    fn testing_sqrt_seven() {
+        let root = Quadratic::sqrt(7, 7, 8).unwrap();
+        let square = root.mul(&root);
+        assert_eq!(square.a.v, Some(Frac { numer: 7, denom: 1 }));
+        assert_eq!(square.a.valuation(), Some(1));
+        assert_eq!(square.b.valuation(), None);
+        assert_eq!(root.valuation(), Some(1));
+        assert_eq!(root.trace().valuation(), None);
+        assert_eq!(
+            root.norm().v,
+            Some(Frac {
+                numer: -7,
+                denom: 1
+            })
+        );
+
+        let inv = root.inverse().unwrap();
+        assert_eq!(inv.b.v, Some(Frac { numer: 1, denom: 7 }));
+        assert_eq!(inv.valuation(), Some(-1));
+        let one = root.mul(&inv);
+        assert_eq!(one.a.v, Some(Frac { numer: 1, denom: 1 }));
+        assert_eq!(one.a.normalized().expanded[0], 1);
+        assert_eq!(one.b.valuation(), None);
+    }
+
+    #[test]
+    fn testing_unramified() {
+        let x = Quadratic::from_frac(
+            &Frac { numer: 1, denom: 2 },
+            &Frac { numer: 7, denom: 1 },
+            3,
+            7,
+            10,
+        )
+        .unwrap();
+        assert_eq!(x.ramification, Ramification::Unramified);
+        assert_eq!(x.valuation(), Some(0));
+        assert_eq!(
+            x.norm().v,
+            Some(Frac {
+                numer: -587,
+                denom: 4
+            })
+        );
+        let y = x.inverse().unwrap();
+        let one = x.mul(&y);
+        assert_eq!(
+            one.a.expanded,
+            expand(&Frac { numer: 1, denom: 1 }, 7, one.a.expanded.len()).expanded
+        );
+        assert_eq!(one.b.valuation(), None);
+
+        let seven = Quadratic::from_frac(
+            &Frac { numer: 7, denom: 1 },
+            &Frac { numer: 0, denom: 1 },
+            3,
+            7,
+            10,
+        )
+        .unwrap();
+        assert_eq!(seven.valuation(), Some(1));
+    }
+}
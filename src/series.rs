@@ -0,0 +1,159 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+
+// returned when the valuations of the terms stop growing, after `terms` terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Divergence {
+    pub terms: i64,
+}
+
+const FIRST_BLOCK: i64 = 8;
+const MAX_TERMS: i64 = 1 << 20;
+
+// sum of a_n for n >= start mod p^digits. The terms are taken in blocks of doubling length,
+// each summed in parallel; once a whole block has valuation >= digits the tail is taken to
+// vanish, and when the smallest valuation of a block is no larger than three blocks earlier
+// the series is reported as divergent. Both are judged from the terms seen, so a series whose
+// valuations plateau for that long before growing again is misjudged.
+#[allow(dead_code)]
+pub fn sum_series<F>(term: F, start: i64, p: i64, digits: usize) -> Result<Padic, Divergence>
+where
+    F: Fn(i64) -> Padic + Sync,
+{
+    let mut sum = expand(&Frac { numer: 0, denom: 1 }, p, digits);
+    let mut minima: Vec<i64> = Vec::new();
+    let mut n = start;
+    let mut len = FIRST_BLOCK;
+    loop {
+        let terms: Vec<Padic> = map_range(n..n + len, len as usize * digits.pow(2), &term);
+        let min = terms
+            .iter()
+            .filter_map(|a| a.valuation())
+            .min()
+            .unwrap_or(i64::MAX);
+        sum = terms.iter().fold(sum, |acc, a| acc.add(a));
+        n += len;
+        if min >= digits as i64 {
+            break;
+        }
+        if minima.len() >= 3 && min <= minima[minima.len() - 3] || n - start >= MAX_TERMS {
+            return Err(Divergence { terms: n - start });
+        }
+        minima.push(min);
+        len *= 2;
+    }
+    let sum = sum.normalized();
+    let known = (digits as i64 - sum.offset).clamp(0, sum.expanded.len() as i64) as usize;
+    Ok(Padic::with_digits(
+        p,
+        sum.expanded[..known].to_vec(),
+        sum.offset,
+        None,
+    ))
+}
+
+// the same for rational terms, each expanded far enough to be known mod p^digits
+#[allow(dead_code)]
+pub fn sum_frac_series<F>(term: F, start: i64, p: i64, digits: usize) -> Result<Padic, Divergence>
+where
+    F: Fn(i64) -> Frac + Sync,
+{
+    sum_series(
+        |n| {
+            let a = term(n);
+            let shift = a.valuation(p).map_or(0, |v| (-v).max(0)) as usize;
+            expand(&a, p, digits + shift)
+        },
+        start,
+        p,
+        digits,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int(n: i64, p: i64) -> Padic {
+        expand(&Frac { numer: n, denom: 1 }, p, 12)
+    }
+
+    #[test]
+    fn testing_factorial_series() {
+        // sum n! in Z_5, compared against the partial sum mod 5^8 up to 40! = 0 mod 5^9
+        let p = 5;
+        let sum = sum_series(
+            |n| (1..=n).fold(int(1, p), |acc, k| acc.mul(&int(k, p))),
+            0,
+            p,
+            8,
+        )
+        .unwrap();
+        let m = 5i128.pow(8);
+        let mut expected = 0i128;
+        let mut factorial = 1i128;
+        for n in 0..40 {
+            if n > 0 {
+                factorial = factorial * n % m;
+            }
+            expected = (expected + factorial) % m;
+        }
+        let expected = expand(
+            &Frac {
+                numer: expected as i64,
+                denom: 1,
+            },
+            p,
+            8,
+        );
+        assert_eq!(sum.offset, 0);
+        assert_eq!(sum.expanded, expected.expanded);
+    }
+
+    #[test]
+    fn testing_logarithm_series() {
+        // log(1 + x) = sum (-1)^(n+1) x^n / n for x in 3Z_3, and log(16) = 2 log(4)
+        let p = 3;
+        let log = |x: i64| {
+            move |n: i64| {
+                let sign = if n % 2 == 1 { 1 } else { -1 };
+                int(x, p).pow(n).div(&int(sign * n, p)).unwrap()
+            }
+        };
+        let log4 = sum_series(log(3), 1, p, 8).unwrap();
+        let log16 = sum_series(log(15), 1, p, 8).unwrap();
+        assert_eq!(log16.sub(&log4.add(&log4)).valuation(), None);
+
+        // sum p^n / n as fractions, which is -log(1 - 3)
+        let s = sum_frac_series(
+            |n| Frac {
+                numer: 3i64.pow(n as u32),
+                denom: n,
+            },
+            1,
+            p,
+            6,
+        )
+        .unwrap();
+        let log_minus_two = sum_series(log(-3), 1, p, 6).unwrap();
+        assert_eq!(s.add(&log_minus_two).valuation(), None);
+    }
+
+    #[test]
+    fn testing_divergence() {
+        assert!(sum_frac_series(|n| Frac { numer: n, denom: 1 }, 0, 5, 6).is_err());
+        assert!(sum_frac_series(|n| Frac { numer: 1, denom: n }, 1, 5, 6).is_err());
+        // the terms 5^n vanish quickly
+        assert!(sum_frac_series(
+            |n| Frac {
+                numer: 5i64.pow(n as u32),
+                denom: 1
+            },
+            0,
+            5,
+            6
+        )
+        .is_ok());
+    }
+}
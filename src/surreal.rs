@@ -1,9 +1,18 @@
+use crate::dispatch::*;
+use crate::dyadic::*;
+use crate::frac::*;
 use core::cmp::Ordering;
 use core::panic;
 use rayon::prelude::*;
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::str::Chars;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+// == compares values through the game order, as partial_cmp does, and hashes the canonical
+// form; structural_eq and Form compare the options themselves
+#[derive(Debug, Clone)]
 pub struct Surreal {
     pub l: Option<Vec<SurrealValue>>,
     pub r: Option<Vec<SurrealValue>>,
@@ -11,33 +20,151 @@ pub struct Surreal {
 
 #[derive(Debug, Clone)]
 pub enum SurrealValue {
-    Float(f64),
+    Dyadic(Dyadic),
     Surreal(Surreal),
 }
 
 impl Eq for SurrealValue {}
 
+// a leaf equals any form of its number, e.g. 1 and { -1, 0 | }
 impl PartialEq for SurrealValue {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
-            (SurrealValue::Float(f1), SurrealValue::Float(f2)) => f1 == f2,
-            (SurrealValue::Surreal(s1), SurrealValue::Surreal(s2)) => s1 == s2,
-            _ => false,
+            (SurrealValue::Dyadic(d1), SurrealValue::Dyadic(d2)) => d1 == d2,
+            _ => le_values(self, other) && le_values(other, self),
         }
     }
 }
 
-impl PartialOrd for SurrealValue {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        match (self, other) {
-            (SurrealValue::Float(f1), SurrealValue::Float(f2)) => f1.partial_cmp(f2),
-            (SurrealValue::Float(_), SurrealValue::Surreal(_)) => Some(Ordering::Less),
-            (SurrealValue::Surreal(_), SurrealValue::Float(_)) => Some(Ordering::Greater),
-            (SurrealValue::Surreal(s1), SurrealValue::Surreal(s2)) => s1.partial_cmp(s2),
+impl Eq for Surreal {}
+
+impl PartialEq for Surreal {
+    fn eq(&self, other: &Self) -> bool {
+        value_eq(self, other)
+    }
+}
+
+// same options in any order and with any repeats, leaves only equal to leaves
+fn form_eq(x: &SurrealValue, y: &SurrealValue) -> bool {
+    match (x, y) {
+        (SurrealValue::Dyadic(d1), SurrealValue::Dyadic(d2)) => d1 == d2,
+        (SurrealValue::Surreal(s1), SurrealValue::Surreal(s2)) => structural_eq(s1, s2),
+        _ => false,
+    }
+}
+
+// options are hashed separately and combined as a sorted set, so that the order and repeats
+// of options, which structural_eq ignores, do not change the hash
+fn hash_options<H: Hasher>(side: &Option<Vec<SurrealValue>>, state: &mut H) {
+    let mut hashes: Vec<u64> = side
+        .iter()
+        .flatten()
+        .map(|v| {
+            let mut h = DefaultHasher::new();
+            hash_form(v, &mut h);
+            h.finish()
+        })
+        .collect();
+    hashes.sort_unstable();
+    hashes.dedup();
+    hashes.hash(state);
+}
+
+fn hash_form<H: Hasher>(v: &SurrealValue, state: &mut H) {
+    match v {
+        SurrealValue::Dyadic(d) => (0u8, d).hash(state),
+        SurrealValue::Surreal(s) => {
+            1u8.hash(state);
+            hash_options(&s.l, state);
+            hash_options(&s.r, state);
         }
     }
 }
 
+// equal values share their canonical form, a number being a leaf
+impl Hash for SurrealValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_form(&canonical_value(self), state);
+    }
+}
+
+impl Hash for Surreal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_form(&simplify(canonical(self)), state);
+    }
+}
+
+// a value compared and hashed by its form, for the tables of sums and products where
+// canonical forms would cost more than they save
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct Form(pub SurrealValue);
+
+impl PartialEq for Form {
+    fn eq(&self, other: &Self) -> bool {
+        form_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Form {}
+
+impl Hash for Form {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_form(&self.0, state);
+    }
+}
+
+// the four outcomes of comparing games; Fuzzy when neither x <= y nor y <= x, as for * and 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameOrdering {
+    Less,
+    Equal,
+    Greater,
+    Fuzzy,
+}
+
+#[allow(dead_code)]
+impl GameOrdering {
+    fn from_le(le: bool, ge: bool) -> GameOrdering {
+        match (le, ge) {
+            (true, true) => GameOrdering::Equal,
+            (true, false) => GameOrdering::Less,
+            (false, true) => GameOrdering::Greater,
+            (false, false) => GameOrdering::Fuzzy,
+        }
+    }
+
+    pub fn to_ordering(self) -> Option<Ordering> {
+        match self {
+            GameOrdering::Less => Some(Ordering::Less),
+            GameOrdering::Equal => Some(Ordering::Equal),
+            GameOrdering::Greater => Some(Ordering::Greater),
+            GameOrdering::Fuzzy => None,
+        }
+    }
+
+    pub fn reverse(self) -> GameOrdering {
+        match self {
+            GameOrdering::Less => GameOrdering::Greater,
+            GameOrdering::Greater => GameOrdering::Less,
+            o => o,
+        }
+    }
+}
+
+// the game order, leaves counting as numbers; Equal exactly when ==
+impl PartialOrd for SurrealValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        GameOrdering::from_le(le_values(self, other), le_values(other, self)).to_ordering()
+    }
+}
+
+impl PartialOrd for Surreal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        game_cmp(self, other).to_ordering()
+    }
+}
+
 #[allow(dead_code)]
 pub fn construct(num: &str) -> Surreal {
     fn parse_value(chars: &mut Chars) -> SurrealValue {
@@ -54,9 +181,9 @@ pub fn construct(num: &str) -> Surreal {
         }
         buffer
             .trim()
-            .parse::<f64>()
-            .map(SurrealValue::Float)
-            .expect("Invalid float value")
+            .parse::<Dyadic>()
+            .map(SurrealValue::Dyadic)
+            .expect("Invalid dyadic value")
     }
 
     fn parse_surreal(chars: &mut Chars) -> Surreal {
@@ -103,48 +230,66 @@ pub fn construct(num: &str) -> Surreal {
 #[allow(dead_code)]
 fn value_to_string(v: &SurrealValue) -> String {
     match v {
-        SurrealValue::Float(i) => i.to_string(),
+        SurrealValue::Dyadic(d) => d.to_string(),
         SurrealValue::Surreal(s) => format!("{}", s),
     }
 }
 
+// number of values in the game tree, the work estimate for a traversal
+#[allow(dead_code)]
+pub fn size(n: &Surreal) -> usize {
+    [&n.l, &n.r]
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|v| match v {
+            SurrealValue::Dyadic(_) => 1,
+            SurrealValue::Surreal(s) => 1 + size(s),
+        })
+        .sum()
+}
+
 #[allow(dead_code)]
 pub fn negate(n: &Surreal) -> Surreal {
+    negate_in(n, parallel(size(n)))
+}
+
+// once a subtree is small enough to walk sequentially so is everything below it, which saves
+// sizing it again
+fn negate_in(n: &Surreal, par: bool) -> Surreal {
     let Surreal { l, r } = n;
     let negated = |val: &SurrealValue| -> SurrealValue {
         match val {
-            SurrealValue::Float(i) => SurrealValue::Float(-i),
-            SurrealValue::Surreal(s) => SurrealValue::Surreal(negate(s)),
+            SurrealValue::Dyadic(d) => SurrealValue::Dyadic(d.neg()),
+            SurrealValue::Surreal(s) if par => SurrealValue::Surreal(negate(s)),
+            SurrealValue::Surreal(s) => SurrealValue::Surreal(negate_in(s, false)),
         }
     };
-    let (nlhs, nrhs) = rayon::join(
-        || l.as_ref().map(|v| v.par_iter().map(negated).collect()),
-        || r.as_ref().map(|v| v.par_iter().map(negated).collect()),
-    );
+    let (nlhs, nrhs) = if par {
+        rayon::join(
+            || l.as_ref().map(|v| v.par_iter().map(negated).collect()),
+            || r.as_ref().map(|v| v.par_iter().map(negated).collect()),
+        )
+    } else {
+        (
+            l.as_ref().map(|v| v.iter().map(negated).collect()),
+            r.as_ref().map(|v| v.iter().map(negated).collect()),
+        )
+    };
     Surreal { l: nrhs, r: nlhs }
 }
 
 impl std::fmt::Display for Surreal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let Surreal { l, r } = self;
-        let left: String = l
-            .as_ref()
-            .map(|v| {
-                v.par_iter()
-                    .map(value_to_string)
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            })
-            .unwrap_or_else(String::new);
-        let right: String = r
-            .as_ref()
-            .map(|v| {
-                v.par_iter()
-                    .map(value_to_string)
-                    .collect::<Vec<String>>()
-                    .join(", ")
-            })
-            .unwrap_or_else(String::new);
+        let work = size(self);
+        let side = |values: &Option<Vec<SurrealValue>>| {
+            values
+                .as_ref()
+                .map(|v| map(v, work, value_to_string).join(", "))
+                .unwrap_or_default()
+        };
+        let (left, right) = (side(l), side(r));
         write!(f, "{{ {} | {} }}", left, right)
     }
 }
@@ -174,56 +319,116 @@ pub fn zero() -> Surreal {
     Surreal { l: None, r: None }
 }
 
+// the simplest number strictly between the largest left option and the smallest right option,
+// None unless every option is a number and the left ones lie below the right ones, e.g. for
+// star(1) = { 0 | 0 }
 #[allow(dead_code)]
-fn convert(n: &Surreal) -> f64 {
-    if n.l.is_none() && n.r.is_none() {
-        return 0.0;
-    }
-    if n.l.is_none() {
-        match &n.r {
-            Some(vec) if vec.len() == 1 => match &vec[0] {
-                SurrealValue::Float(v) => *v - 1.0,
-                _ => panic!("Too complicated"),
-            },
-            _ => panic!("Too complicated"),
-        }
-    } else if n.r.is_none() {
-        match &n.l {
-            Some(vec) if vec.len() == 1 => match &vec[0] {
-                SurrealValue::Float(v) => *v + 1.0,
-                _ => panic!("Too complicated"),
-            },
-            _ => panic!("Too complicated"),
-        }
-    } else {
-        panic!("Too complicated");
+pub fn value(n: &Surreal) -> Option<Dyadic> {
+    let side = |values: &Option<Vec<SurrealValue>>| {
+        values
+            .iter()
+            .flatten()
+            .map(leaf_value)
+            .collect::<Option<Vec<Dyadic>>>()
+    };
+    let (l, r) = (side(&n.l)?, side(&n.r)?);
+    Dyadic::simplest_between(l.into_iter().max(), r.into_iter().min())
+}
+
+fn leaf_value(v: &SurrealValue) -> Option<Dyadic> {
+    match v {
+        SurrealValue::Dyadic(d) => Some(*d),
+        SurrealValue::Surreal(s) => value(s),
     }
 }
 
 #[allow(dead_code)]
-fn increment(side: &Option<Vec<SurrealValue>>, x: f64) -> Vec<SurrealValue> {
+fn increment(side: &Option<Vec<SurrealValue>>, x: Dyadic) -> Vec<SurrealValue> {
     side.as_ref().map_or_else(Vec::new, |values| {
-        values
-            .par_iter()
-            .map(|v| match v {
-                SurrealValue::Float(f) => SurrealValue::Float(f + x),
-                SurrealValue::Surreal(_) => panic!("undefined for nested surreals"),
-            })
-            .collect()
+        map(values, values.len(), |v| match v {
+            SurrealValue::Dyadic(d) => SurrealValue::Dyadic(d.add(&x)),
+            SurrealValue::Surreal(_) => panic!("undefined for nested surreals"),
+        })
     })
 }
 
+// the simplest form of a number, with leaves as options: { n - 1 | } for n > 0, { | n + 1 } for
+// n < 0 and { (m - 1) / 2^k | (m + 1) / 2^k } for m / 2^k
+#[allow(dead_code)]
+pub fn from_dyadic(d: Dyadic) -> Surreal {
+    let (l, r) = d.options();
+    Surreal {
+        l: l.map(|x| vec![SurrealValue::Dyadic(x)]),
+        r: r.map(|x| vec![SurrealValue::Dyadic(x)]),
+    }
+}
+
+// a surreal from its options, repeats dropped and an empty side as None
+fn from_options(l: Vec<SurrealValue>, r: Vec<SurrealValue>) -> Surreal {
+    let side = |values: Vec<SurrealValue>| {
+        let mut seen = HashSet::new();
+        let unique: Vec<SurrealValue> = values
+            .into_iter()
+            .filter(|v| seen.insert(Form(v.clone())))
+            .collect();
+        (!unique.is_empty()).then_some(unique)
+    };
+    Surreal {
+        l: side(l),
+        r: side(r),
+    }
+}
+
+type Memo = HashMap<(Form, Form), SurrealValue>;
+
+// x + y = { X^L + y, x + Y^L | X^R + y, x + Y^R }, a leaf being the simplest form of its
+// number; two numbers add as dyadics
+fn add_values(x: &SurrealValue, y: &SurrealValue, memo: &mut Memo) -> SurrealValue {
+    if let (Some(a), Some(b)) = (leaf_value(x), leaf_value(y)) {
+        return SurrealValue::Dyadic(a.add(&b));
+    }
+    let key = (Form(x.clone()), Form(y.clone()));
+    if let Some(sum) = memo.get(&key) {
+        return sum.clone();
+    }
+    let ((xl, xr), (yl, yr)) = (options(x), options(y));
+    let mut side = |xs: &[SurrealValue], ys: &[SurrealValue]| -> Vec<SurrealValue> {
+        let mut sums: Vec<SurrealValue> = xs.iter().map(|a| add_values(a, y, memo)).collect();
+        sums.extend(ys.iter().map(|b| add_values(x, b, memo)));
+        sums
+    };
+    let (l, r) = (side(&xl, &yl), side(&xr, &yr));
+    let sum = SurrealValue::Surreal(from_options(l, r));
+    memo.insert(key, sum.clone());
+    sum
+}
+
+// the left and right options of the sum are built in parallel for large games, each side with
+// its own table of the sums met so far; the sum comes back in canonical form
 #[allow(dead_code)]
 pub fn add(n1: &Surreal, n2: &Surreal) -> Surreal {
-    // definition: x + y = {Xl + y, x + Yl | Xr + y, x + Yr}
-    let x = convert(n1);
-    let y = convert(n2);
-    pdt_add(n1, x, n2, y)
+    let (x, y) = (
+        SurrealValue::Surreal(n1.clone()),
+        SurrealValue::Surreal(n2.clone()),
+    );
+    if let (Some(a), Some(b)) = (value(n1), value(n2)) {
+        return from_dyadic(a.add(&b));
+    }
+    let ((xl, xr), (yl, yr)) = (options(&x), options(&y));
+    let side = |xs: &[SurrealValue], ys: &[SurrealValue]| -> Vec<SurrealValue> {
+        let mut memo = Memo::new();
+        let mut sums: Vec<SurrealValue> = xs.iter().map(|a| add_values(a, &y, &mut memo)).collect();
+        sums.extend(ys.iter().map(|b| add_values(&x, b, &mut memo)));
+        sums
+    };
+    let (l, r) = join(size(n1) + size(n2), || side(&xl, &yl), || side(&xr, &yr));
+    canonical(&from_options(l, r))
 }
 
 #[allow(dead_code)]
-pub fn pdt_add(n1: &Surreal, x: f64, n2: &Surreal, y: f64) -> Surreal {
-    let (left, right): (Vec<SurrealValue>, Vec<SurrealValue>) = rayon::join(
+pub fn pdt_add(n1: &Surreal, x: Dyadic, n2: &Surreal, y: Dyadic) -> Surreal {
+    let (left, right): (Vec<SurrealValue>, Vec<SurrealValue>) = join(
+        size(n1) + size(n2),
         || {
             increment(&n1.l, y)
                 .into_iter()
@@ -231,7 +436,7 @@ pub fn pdt_add(n1: &Surreal, x: f64, n2: &Surreal, y: f64) -> Surreal {
                 .collect()
         },
         || {
-            increment(&n1.r, x)
+            increment(&n1.r, y)
                 .into_iter()
                 .chain(increment(&n2.r, x))
                 .collect()
@@ -255,27 +460,72 @@ pub fn pdt_add(n1: &Surreal, x: f64, n2: &Surreal, y: f64) -> Surreal {
     }
 }
 
+// the same options on each side, regardless of their order, repeats or an empty side given
+// as None or Some(vec![]); leaves are only equal to leaves
 #[allow(dead_code)]
-pub fn eq(n1: &Surreal, n2: &Surreal) -> bool {
-    n1.l.par_iter().all(|x| n2.l.par_iter().any(|y| x == y))
-        && n1.r.par_iter().all(|x| n2.r.par_iter().any(|y| x == y))
+pub fn structural_eq(n1: &Surreal, n2: &Surreal) -> bool {
+    let same = |a: &Option<Vec<SurrealValue>>, b: &Option<Vec<SurrealValue>>| {
+        let (a, b) = (a.as_deref().unwrap_or(&[]), b.as_deref().unwrap_or(&[]));
+        let within = |a: &[SurrealValue], b: &[SurrealValue]| {
+            a.iter().all(|x| b.iter().any(|y| form_eq(x, y)))
+        };
+        within(a, b) && within(b, a)
+    };
+    same(&n1.l, &n2.l) && same(&n1.r, &n2.r)
 }
 
+// n1 <= n2 and n2 <= n1, e.g. { 0 | } and { -1, 0 | } are both 1
 #[allow(dead_code)]
-pub fn le(n1: &Surreal, n2: &Surreal) -> bool {
-    let x = convert(n1);
-    let y = convert(n2);
+pub fn value_eq(n1: &Surreal, n2: &Surreal) -> bool {
+    game_cmp(n1, n2) == GameOrdering::Equal
+}
+
+// the options of a value, a leaf standing for its number in simplest form:
+// n + 1 = { n | }, -n - 1 = { | -n } and m / 2^k = { (m - 1) / 2^k | (m + 1) / 2^k } for odd m
+fn options(v: &SurrealValue) -> (Cow<'_, [SurrealValue]>, Cow<'_, [SurrealValue]>) {
+    match v {
+        SurrealValue::Surreal(s) => (
+            Cow::Borrowed(s.l.as_deref().unwrap_or(&[])),
+            Cow::Borrowed(s.r.as_deref().unwrap_or(&[])),
+        ),
+        SurrealValue::Dyadic(x) => {
+            let (l, r) = x.options();
+            let leaves =
+                |d: Option<Dyadic>| Cow::Owned(d.map(SurrealValue::Dyadic).into_iter().collect());
+            (leaves(l), leaves(r))
+        }
+    }
+}
 
-    let (check_left, check_right): (bool, bool) = rayon::join(
+// Conway: x <= y unless some x^L >= y or some y^R <= x
+fn le_values(x: &SurrealValue, y: &SurrealValue) -> bool {
+    if let (SurrealValue::Dyadic(a), SurrealValue::Dyadic(b)) = (x, y) {
+        return a <= b;
+    }
+    let (xl, _) = options(x);
+    let (_, yr) = options(y);
+    !xl.iter().any(|l| le_values(y, l)) && !yr.iter().any(|r| le_values(r, x))
+}
+
+// only the options of n1 and n2 themselves are checked in parallel, the recursion below them
+// runs sequentially
+#[allow(dead_code)]
+pub fn le(n1: &Surreal, n2: &Surreal) -> bool {
+    if let (Some(a), Some(b)) = (value(n1), value(n2)) {
+        return a <= b;
+    }
+    let x = SurrealValue::Surreal(n1.clone());
+    let y = SurrealValue::Surreal(n2.clone());
+    let work = size(n1) + size(n2);
+    let (check_left, check_right): (bool, bool) = join(
+        work,
         || {
-            n1.l.as_ref().map_or(true, |l_vals| {
-                l_vals.par_iter().all(|v| v <= &SurrealValue::Float(y))
-            })
+            n1.l.as_ref()
+                .is_none_or(|l_vals| all(l_vals, work, |v| !le_values(&y, v)))
         },
         || {
-            n2.r.as_ref().map_or(true, |r_vals| {
-                r_vals.par_iter().all(|v| &SurrealValue::Float(x) <= v)
-            })
+            n2.r.as_ref()
+                .is_none_or(|r_vals| all(r_vals, work, |v| !le_values(v, &x)))
         },
     );
 
@@ -287,30 +537,323 @@ pub fn ge(n1: &Surreal, n2: &Surreal) -> bool {
     le(n2, n1)
 }
 
+#[allow(dead_code)]
+pub fn game_cmp(n1: &Surreal, n2: &Surreal) -> GameOrdering {
+    let (a, b) = join(size(n1) + size(n2), || le(n1, n2), || le(n2, n1));
+    GameOrdering::from_le(a, b)
+}
+
+// for games x < y means x <= y without y <= x, so x and y are not confused
 #[allow(dead_code)]
 pub fn lt(n1: &Surreal, n2: &Surreal) -> bool {
-    let x = convert(n1);
-    let y = convert(n2);
+    game_cmp(n1, n2) == GameOrdering::Less
+}
 
-    let (check_left, check_right): (bool, bool) = rayon::join(
-        || {
-            n1.l.as_ref().map_or(true, |l_vals| {
-                l_vals.par_iter().all(|v| v < &SurrealValue::Float(y))
-            })
-        },
-        || {
-            n2.r.as_ref().map_or(true, |r_vals| {
-                r_vals.par_iter().all(|v| &SurrealValue::Float(x) < v)
+#[allow(dead_code)]
+pub fn gt(n1: &Surreal, n2: &Surreal) -> bool {
+    lt(n2, n1)
+}
+
+fn negate_value(v: &SurrealValue) -> SurrealValue {
+    match v {
+        SurrealValue::Dyadic(d) => SurrealValue::Dyadic(d.neg()),
+        SurrealValue::Surreal(s) => SurrealValue::Surreal(negate(s)),
+    }
+}
+
+// a product is kept as a leaf when it is a number, which stops products of products from
+// growing trees of their own
+fn simplify(form: Surreal) -> SurrealValue {
+    match value(&form) {
+        Some(d) => SurrealValue::Dyadic(d),
+        None => SurrealValue::Surreal(form),
+    }
+}
+
+// leaves first in increasing order, then games by how they print, so that canonical forms
+// print the same way however they were built
+fn sort_options(side: &mut [SurrealValue]) {
+    side.sort_by(|a, b| match (a, b) {
+        (SurrealValue::Dyadic(x), SurrealValue::Dyadic(y)) => x.cmp(y),
+        (SurrealValue::Dyadic(_), SurrealValue::Surreal(_)) => Ordering::Less,
+        (SurrealValue::Surreal(_), SurrealValue::Dyadic(_)) => Ordering::Greater,
+        (SurrealValue::Surreal(x), SurrealValue::Surreal(y)) => x.to_string().cmp(&y.to_string()),
+    });
+}
+
+fn canonical_value(v: &SurrealValue) -> SurrealValue {
+    match v {
+        SurrealValue::Dyadic(d) => SurrealValue::Dyadic(*d),
+        SurrealValue::Surreal(s) => simplify(canonical(s)),
+    }
+}
+
+// the unique simplest form of a game, with canonical options, numbers as leaves:
+// a left option x^L is reversible through x^LR <= x and is replaced by the left options of
+// x^LR, a right option x^R through x^RL >= x by the right options of x^RL, and options
+// dominated by another one on their side, x^L <= x^L' or x^R >= x^R', are dropped.
+// Two games are equal in value exactly when their canonical forms are equal, e.g.
+// { * | * } reduces to { | } = 0 and { -1, 0 | 1, 2 } to { 0 | 1 } = 1/2
+#[allow(dead_code)]
+pub fn canonical(n: &Surreal) -> Surreal {
+    let work = size(n);
+    let side = |values: &Option<Vec<SurrealValue>>| {
+        map(values.as_deref().unwrap_or(&[]), work, canonical_value)
+    };
+    let (mut l, mut r) = join(work, || side(&n.l), || side(&n.r));
+
+    // bypassing reversible options keeps the value, so x may be any form seen so far; the
+    // options brought in are canonical but may be reversible in turn
+    loop {
+        let x = SurrealValue::Surreal(from_options(l.clone(), r.clone()));
+        let mut reversed = false;
+        let mut bypass = |values: &[SurrealValue], left: bool| -> Vec<SurrealValue> {
+            let mut bypassed = Vec::new();
+            for v in values {
+                let (vl, vr) = options(v);
+                let through = if left {
+                    vr.iter().find(|w| le_values(w, &x))
+                } else {
+                    vl.iter().find(|w| le_values(&x, w))
+                };
+                match through {
+                    Some(w) => {
+                        reversed = true;
+                        let (wl, wr) = options(w);
+                        bypassed.extend(if left { wl } else { wr }.iter().cloned());
+                    }
+                    None => bypassed.push(v.clone()),
+                }
+            }
+            bypassed
+        };
+        let (new_l, new_r) = (bypass(&l, true), bypass(&r, false));
+        if !reversed {
+            break;
+        }
+        (l, r) = (new_l, new_r);
+    }
+
+    // distinct canonical options are distinct in value, so domination is strict
+    let form = from_options(l, r);
+    let undominated = |values: &Option<Vec<SurrealValue>>, left: bool| {
+        let values = values.as_deref().unwrap_or(&[]);
+        let mut kept: Vec<SurrealValue> = values
+            .iter()
+            .enumerate()
+            .filter(|&(i, v)| {
+                !values.iter().enumerate().any(|(j, w)| {
+                    i != j
+                        && if left {
+                            le_values(v, w)
+                        } else {
+                            le_values(w, v)
+                        }
+                })
             })
-        },
+            .map(|(_, v)| v.clone())
+            .collect();
+        sort_options(&mut kept);
+        kept
+    };
+    let form = from_options(undominated(&form.l, true), undominated(&form.r, false));
+    match value(&form) {
+        Some(d) => from_dyadic(d),
+        None => form,
+    }
+}
+
+// the sums and products met while multiplying
+#[derive(Default)]
+struct Tables {
+    sums: Memo,
+    products: Memo,
+}
+
+// the option a y + x b - a b of xy, for an option a of x and b of y
+fn product_option(
+    x: &SurrealValue,
+    y: &SurrealValue,
+    a: &SurrealValue,
+    b: &SurrealValue,
+    tables: &mut Tables,
+) -> SurrealValue {
+    let ay = mul_values(a, y, tables);
+    let xb = mul_values(x, b, tables);
+    let ab = negate_value(&mul_values(a, b, tables));
+    let sum = add_values(&ay, &xb, &mut tables.sums);
+    add_values(&sum, &ab, &mut tables.sums)
+}
+
+// the options of xy on one side, from the pairs of options of x and y named by `pairs`
+fn product_side(
+    x: &SurrealValue,
+    y: &SurrealValue,
+    pairs: [(&[SurrealValue], &[SurrealValue]); 2],
+    tables: &mut Tables,
+) -> Vec<SurrealValue> {
+    let mut side = Vec::new();
+    for (xs, ys) in pairs {
+        for a in xs {
+            for b in ys {
+                side.push(product_option(x, y, a, b, tables));
+            }
+        }
+    }
+    side
+}
+
+// xy = { X^L y + x Y^L - X^L Y^L, X^R y + x Y^R - X^R Y^R |
+//        X^L y + x Y^R - X^L Y^R, X^R y + x Y^L - X^R Y^L }
+fn mul_values(x: &SurrealValue, y: &SurrealValue, tables: &mut Tables) -> SurrealValue {
+    if let (Some(a), Some(b)) = (leaf_value(x), leaf_value(y)) {
+        return SurrealValue::Dyadic(a.mul(&b));
+    }
+    let key = (Form(x.clone()), Form(y.clone()));
+    if let Some(product) = tables.products.get(&key) {
+        return product.clone();
+    }
+    let ((xl, xr), (yl, yr)) = (options(x), options(y));
+    let l = product_side(x, y, [(&xl, &yl), (&xr, &yr)], tables);
+    let r = product_side(x, y, [(&xl, &yr), (&xr, &yl)], tables);
+    let product = simplify(from_options(l, r));
+    tables.products.insert(key, product.clone());
+    product
+}
+
+// Conway's product, numbers multiplying as dyadics; for games that are not numbers the
+// product depends on the forms, not only on the values. Both sides of the product are built
+// in parallel for large games, and the product comes back in canonical form
+#[allow(dead_code)]
+pub fn mul(n1: &Surreal, n2: &Surreal) -> Surreal {
+    if let (Some(a), Some(b)) = (value(n1), value(n2)) {
+        return from_dyadic(a.mul(&b));
+    }
+    let (x, y) = (
+        SurrealValue::Surreal(n1.clone()),
+        SurrealValue::Surreal(n2.clone()),
+    );
+    let ((xl, xr), (yl, yr)) = (options(&x), options(&y));
+    let (l, r) = join(
+        size(n1) * size(n2),
+        || product_side(&x, &y, [(&xl, &yl), (&xr, &yr)], &mut Tables::default()),
+        || product_side(&x, &y, [(&xl, &yr), (&xr, &yl)], &mut Tables::default()),
     );
+    canonical(&from_options(l, r))
+}
 
-    check_left && check_right
+// whether a reciprocal or quotient is the exact value, or the number born on the day limit on
+// the way to it when the exact value is born later, e.g. on day ω for 1/3
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Exact,
+    Truncated,
 }
 
+// Conway's construction of y = 1/x for positive x from its positive left options and its right
+// options: 0 is a left option of y, and every option y' of y gives
+//     (1 + (x^R - x) y^L) / x^R and (1 + (x^L - x) y^R) / x^L on the left,
+//     (1 + (x^L - x) y^L) / x^L and (1 + (x^R - x) y^R) / x^R on the right.
+// Each step is increasing in y' for x^R and decreasing for x^L, so only the largest left and
+// smallest right option so far are carried from one generation to the next. The generations
+// stop once the simplest number between them is exact or born after birthday_limit; None for
+// zero, for games that are not numbers and for options with denominators past 2^62, and
+// negative numbers go through -1/(-x)
 #[allow(dead_code)]
-pub fn gt(n1: &Surreal, n2: &Surreal) -> bool {
-    lt(n2, n1)
+pub fn reciprocal(n: &Surreal, birthday_limit: u32) -> Option<(Surreal, Precision)> {
+    let x = value(n)?;
+    let zero = Dyadic::integer(0);
+    if x == zero {
+        return None;
+    }
+    if x < zero {
+        let (y, precision) = reciprocal(&negate(n), birthday_limit)?;
+        return Some((negate(&y), precision));
+    }
+    let side = |values: &Option<Vec<SurrealValue>>| -> Option<Vec<Frac>> {
+        values
+            .iter()
+            .flatten()
+            .filter_map(leaf_value)
+            .filter(|&d| d > zero)
+            .map(|d| d.to_frac())
+            .collect()
+    };
+    let (xl, xr) = (side(&n.l)?, side(&n.r)?);
+    let x = x.to_frac()?;
+    let one = Frac { numer: 1, denom: 1 };
+    // (1 + (x' - x) y) / x'
+    let step = |x_option: &Frac, y: &Frac| {
+        one.checked_add(&x_option.checked_add(&x.neg())?.checked_mul(y)?)?
+            .checked_div(x_option)
+    };
+    let max = |a: Frac, b: Frac| {
+        if a.checked_add(&b.neg())?.numer < 0 {
+            Some(b)
+        } else {
+            Some(a)
+        }
+    };
+    let min = |a: Frac, b: Frac| {
+        if a.checked_add(&b.neg())?.numer > 0 {
+            Some(b)
+        } else {
+            Some(a)
+        }
+    };
+
+    let mut lo = Frac { numer: 0, denom: 1 };
+    let mut hi: Option<Frac> = None;
+    let mut candidate = Dyadic::simplest_between_fracs(Some(lo), hi)?;
+    let precision = loop {
+        if candidate.birthday() > birthday_limit {
+            break Precision::Truncated;
+        }
+        if candidate.to_frac().and_then(|c| c.checked_mul(&x)) == Some(one) {
+            break Precision::Exact;
+        }
+        let next = (|| {
+            let mut new_lo = lo;
+            let mut new_hi = hi;
+            for xr in &xr {
+                new_lo = max(new_lo, step(xr, &lo)?)?;
+                if let Some(h) = hi {
+                    let y = step(xr, &h)?;
+                    new_hi = Some(new_hi.map_or(Some(y), |b| min(b, y))?);
+                }
+            }
+            for xl in &xl {
+                if let Some(h) = hi {
+                    new_lo = max(new_lo, step(xl, &h)?)?;
+                }
+                let y = step(xl, &lo)?;
+                new_hi = Some(new_hi.map_or(Some(y), |b| min(b, y))?);
+            }
+            Some((new_lo, new_hi))
+        })();
+        // the rationals overflowed or stopped improving
+        let Some((new_lo, new_hi)) = next.filter(|&(l, h)| (l, h) != (lo, hi)) else {
+            break Precision::Truncated;
+        };
+        (lo, hi) = (new_lo, new_hi);
+        candidate = Dyadic::simplest_between_fracs(Some(lo), hi)?;
+    };
+    Some((from_dyadic(candidate.truncate(birthday_limit)), precision))
+}
+
+// n1 times the reciprocal of n2, exact when the reciprocal is or n1 is 0
+#[allow(dead_code)]
+pub fn div(n1: &Surreal, n2: &Surreal, birthday_limit: u32) -> Option<(Surreal, Precision)> {
+    let (y, precision) = reciprocal(n2, birthday_limit)?;
+    let exact = precision == Precision::Exact || value(n1) == Some(Dyadic::integer(0));
+    Some((
+        mul(n1, &y),
+        if exact {
+            Precision::Exact
+        } else {
+            Precision::Truncated
+        },
+    ))
 }
 
 #[allow(dead_code)]
@@ -329,48 +872,53 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pstar::star;
+    use std::collections::HashSet;
 
     #[test]
     fn testing_construct() {
         let result = construct("{ | }");
-        assert_eq!(result, zero());
+        assert!(structural_eq(&result, &zero()));
         let result2 = construct("{ 0.0 | { 1 | } }");
-        assert_eq!(
-            result2,
-            Surreal {
-                l: Some(vec![SurrealValue::Float(0.0)]),
+        assert!(structural_eq(
+            &result2,
+            &Surreal {
+                l: Some(vec![SurrealValue::Dyadic(Dyadic::integer(0))]),
                 r: Some(vec![SurrealValue::Surreal(Surreal {
-                    l: Some(vec![SurrealValue::Float(1.0)]),
+                    l: Some(vec![SurrealValue::Dyadic(Dyadic::integer(1))]),
                     r: None,
                 })]),
             }
-        );
+        ));
         let result3 = construct("{ 2.0, 3.0, 4.0 | 9.0, 2.0 }");
-        assert_eq!(
-            result3,
-            Surreal {
+        assert!(structural_eq(
+            &result3,
+            &Surreal {
                 l: Some(vec![
-                    SurrealValue::Float(2.0),
-                    SurrealValue::Float(3.0),
-                    SurrealValue::Float(4.0),
+                    SurrealValue::Dyadic(Dyadic::integer(2)),
+                    SurrealValue::Dyadic(Dyadic::integer(3)),
+                    SurrealValue::Dyadic(Dyadic::integer(4)),
+                ]),
+                r: Some(vec![
+                    SurrealValue::Dyadic(Dyadic::integer(9)),
+                    SurrealValue::Dyadic(Dyadic::integer(2)),
                 ]),
-                r: Some(vec![SurrealValue::Float(9.0), SurrealValue::Float(2.0),]),
             }
-        );
+        ));
         let result4 = construct("{ | { | { | 6 } } }");
-        assert_eq!(
-            result4,
-            Surreal {
+        assert!(structural_eq(
+            &result4,
+            &Surreal {
                 l: None,
                 r: Some(vec![SurrealValue::Surreal(Surreal {
                     l: None,
                     r: Some(vec![SurrealValue::Surreal(Surreal {
                         l: None,
-                        r: Some(vec![SurrealValue::Float(6.0),]),
+                        r: Some(vec![SurrealValue::Dyadic(Dyadic::integer(6)),]),
                     })]),
                 })]),
             }
-        );
+        ));
     }
 
     #[test]
@@ -386,27 +934,51 @@ mod tests {
     #[test]
     fn testing_append() {
         let mut result = construct("{ | }");
-        append(&mut result, SurrealValue::Float(0.0), true);
-        assert_eq!(result, construct("{ 0.0 | }"));
+        append(&mut result, SurrealValue::Dyadic(Dyadic::integer(0)), true);
+        assert!(structural_eq(&result, &construct("{ 0.0 | }")));
         append(
             &mut result,
             SurrealValue::Surreal(Surreal {
                 l: None,
-                r: Some(vec![SurrealValue::Float(9.0)]),
+                r: Some(vec![SurrealValue::Dyadic(Dyadic::integer(9))]),
             }),
             false,
         );
-        assert_eq!(result, construct("{ 0.0 | { | 9.0 } }"));
+        assert!(structural_eq(&result, &construct("{ 0.0 | { | 9.0 } }")));
     }
 
     #[test]
     fn testing_conversion() {
-        assert_eq!(convert(&construct("{ | }")), 0.0);
-        assert_eq!(convert(&construct("{0.0 | }")), 1.0);
-        assert_eq!(convert(&construct("{4.0 | }")), 5.0);
-        assert_eq!(convert(&construct("{ | 0.0}")), -1.0);
-        assert_eq!(convert(&construct("{ | -2.0}")), -3.0);
-        assert_eq!(convert(&construct("{ | -20.0}")), -21.0);
+        let int = |n: i64| Some(Dyadic::integer(n));
+        assert_eq!(value(&construct("{ | }")), int(0));
+        assert_eq!(value(&construct("{0.0 | }")), int(1));
+        assert_eq!(value(&construct("{4.0 | }")), int(5));
+        assert_eq!(value(&construct("{ | 0.0}")), int(-1));
+        assert_eq!(value(&construct("{ | -2.0}")), int(-3));
+        assert_eq!(value(&construct("{ | -20.0}")), int(-21));
+        assert_eq!(value(&construct("{ 3.5, 1 | }")), int(4));
+        assert_eq!(
+            value(&construct("{ -3 | -2.5 }")),
+            Some(Dyadic::new(-11, 2))
+        );
+        assert_eq!(value(&construct("{ 0 | 1 }")), Some(Dyadic::new(1, 1)));
+        assert_eq!(value(&construct("{ 0 | 0.5 }")), Some(Dyadic::new(1, 2)));
+        assert_eq!(
+            value(&construct("{ 0.25 | 0.375 }")),
+            Some(Dyadic::new(5, 4))
+        );
+        assert_eq!(
+            value(&construct("{ -1, { -1 | 1 } | { 1 | 2 }, 1 }")),
+            Some(Dyadic::new(1, 1))
+        );
+        assert_eq!(value(&construct("{ { | 7 } | { { 0 | } | } }")), int(1));
+        assert_eq!(value(&construct("{ { { 0 | } | } | }")), int(3));
+        // not numbers: *, a form with a left option above a right one, and up = { 0 | * }
+        assert_eq!(value(&star(1)), None);
+        assert_eq!(value(&construct("{ 1 | 1 }")), None);
+        assert_eq!(value(&construct("{ 2 | 1 }")), None);
+        assert_eq!(value(&construct("{ 0 | { 0 | 0 } }")), None);
+        assert_eq!(value(&star(3)), None);
     }
 
     #[test]
@@ -416,8 +988,8 @@ mod tests {
         let n3 = construct("{ | 0.0 }");
         let n4 = construct("{1.0 | }");
         let n5 = construct("{ | -2.0 }");
-        assert!(eq(&n1, &n1));
-        assert!(eq(&n1, &n2));
+        assert!(structural_eq(&n1, &n1));
+        assert!(structural_eq(&n1, &n2));
         assert!(le(&n1, &n1));
         assert!(le(&n1, &n1));
         assert!(le(&n3, &n1));
@@ -429,6 +1001,134 @@ mod tests {
         assert!(gt(&n4, &n1));
     }
 
+    #[test]
+    fn testing_recursive_comparisons() {
+        let half = construct("{ 0 | 1 }");
+        let zero = zero();
+        let one = construct("{ 0 | }");
+        assert!(lt(&zero, &half) && lt(&half, &one));
+        assert!(le(&half, &construct("{ 0.0 | 1.0 }")));
+        // { | 2 } = 0
+        assert!(lt(&construct("{ | { 1 | } }"), &half));
+        // 1/2 in another form
+        let other_half = construct("{ -1, { -1 | 1 } | { 1 | 2 }, 1 }");
+        assert!(le(&half, &other_half) && ge(&half, &other_half));
+        assert!(lt(&half, &construct("{ 0.5 | 1 }")));
+        // { 0, 2 | } = { 2.5 | } = 3
+        let (a, b) = (construct("{ { | 7 }, 2 | }"), construct("{ 2.5 | }"));
+        assert!(ge(&a, &b) && !gt(&a, &b));
+        assert!(lt(&construct("{ | -2.75 }"), &construct("{ -3 | -2.5 }")));
+
+        // * = { 0 | 0 } is confused with 0 but equal to itself, and *2 is confused with *
+        let s1 = star(1);
+        let s2 = star(2);
+        assert!(!le(&s1, &zero) && !le(&zero, &s1));
+        assert!(le(&s1, &s1) && !lt(&s1, &s1));
+        assert!(!le(&s1, &s2) && !le(&s2, &s1));
+        assert!(lt(&s1, &one) && lt(&construct("{ | 0 }"), &s2));
+        // up = { 0 | * } is positive but below every positive number
+        let up = construct("{ 0 | { 0 | 0 } }");
+        assert!(lt(&zero, &up) && lt(&up, &construct("{ 0 | 0.125 }")));
+        assert!(!le(&up, &s1) && !le(&s1, &up));
+    }
+
+    #[test]
+    fn testing_game_order() {
+        let zero = zero();
+        let half = construct("{ 0 | 1 }");
+        let other_half = construct("{ -1, { -1 | 1 } | { 1 | 2 }, 1 }");
+        assert_eq!(half.partial_cmp(&other_half), Some(Ordering::Equal));
+        assert_eq!(half, other_half);
+        assert!(!structural_eq(&half, &other_half));
+        assert!(zero < half && half <= other_half && half >= other_half);
+        assert_eq!(game_cmp(&half, &zero), GameOrdering::Greater);
+        assert_eq!(game_cmp(&zero, &half), GameOrdering::Less);
+
+        // * is confused with 0, and options compare by value rather than by kind of leaf
+        let s1 = star(1);
+        assert_eq!(s1.partial_cmp(&zero), None);
+        assert_eq!(game_cmp(&s1, &zero), GameOrdering::Fuzzy);
+        assert!([s1 < zero, s1 > zero, s1 <= zero, s1 >= zero] == [false; 4]);
+        assert_eq!(game_cmp(&star(3), &star(3)), GameOrdering::Equal);
+        assert_eq!(game_cmp(&star(3), &star(2)), GameOrdering::Fuzzy);
+        assert_eq!(game_cmp(&star(2), &s1).reverse(), GameOrdering::Fuzzy);
+        assert!(
+            SurrealValue::Surreal(construct("{ 1 | }")) > SurrealValue::Dyadic(Dyadic::new(3, 1))
+        );
+        assert!(SurrealValue::Dyadic(Dyadic::integer(-1)) < SurrealValue::Surreal(zero.clone()));
+        assert_eq!(
+            SurrealValue::Surreal(s1).partial_cmp(&SurrealValue::Dyadic(Dyadic::integer(0))),
+            None
+        );
+    }
+
+    #[test]
+    fn testing_equality() {
+        let one = construct("{ 0 | }");
+        let other_one = construct("{ -1, 0 | }");
+        assert!(value_eq(&one, &other_one));
+        assert!(!structural_eq(&one, &other_one) && one == other_one);
+        // the old check only looked for the options of n1 in n2
+        assert!(!structural_eq(&one, &construct("{ 0, 1 | }")));
+        assert!(!value_eq(&one, &construct("{ 0, 1 | }")));
+        assert!(structural_eq(
+            &construct("{ 1, { | 2 }, 1 | 3 }"),
+            &construct("{ { | 2 }, 1 | 3 }")
+        ));
+        assert!(structural_eq(
+            &Surreal {
+                l: Some(vec![]),
+                r: None
+            },
+            &zero()
+        ));
+        assert!(!structural_eq(
+            &construct("{ 0 | }"),
+            &construct("{ { | } | }")
+        ));
+        // a leaf equals the forms of its number, a Form only the same leaf
+        let leaf = SurrealValue::Dyadic(Dyadic::integer(1));
+        let form = SurrealValue::Surreal(other_one.clone());
+        assert_eq!(leaf, form);
+        assert_ne!(Form(leaf.clone()), Form(form.clone()));
+        assert!(value_eq(&construct("{ 0 | }"), &construct("{ { | } | }")));
+        assert!(value_eq(
+            &star(2),
+            &construct("{ 0, { 0 | 0 } | { 0 | 0 }, 0 }")
+        ));
+        assert!(!value_eq(&star(2), &star(1)));
+
+        let hash = |x: &dyn Fn(&mut DefaultHasher)| {
+            let mut h = DefaultHasher::new();
+            x(&mut h);
+            h.finish()
+        };
+        let a = construct("{ 1, { | 2 }, 1 | 3 }");
+        let b = construct("{ { | 2 }, 1 | 3 }");
+        assert_eq!(hash(&|h| a.hash(h)), hash(&|h| b.hash(h)));
+        assert_eq!(hash(&|h| leaf.hash(h)), hash(&|h| form.hash(h)));
+        assert_eq!(hash(&|h| one.hash(h)), hash(&|h| other_one.hash(h)));
+
+        let forms = [
+            one.clone(),
+            other_one.clone(),
+            construct("{ -1, 0 | }"),
+            construct("{ { | } | }"),
+            construct("{ 0 | 1 }"),
+            construct("{ -1, { -1 | 1 } | { 1 | 2 }, 1 }"),
+            star(1),
+            construct("{ 0 | 0, 0 }"),
+        ];
+        let by_form: HashSet<Form> = forms
+            .iter()
+            .map(|x| Form(SurrealValue::Surreal(x.clone())))
+            .collect();
+        let by_value: HashSet<Surreal> = forms.iter().cloned().collect();
+        assert_eq!(by_form.len(), 6);
+        assert_eq!(by_value.len(), 3);
+        assert!(by_value.contains(&construct("{ 0.5 | }")));
+    }
+
     #[test]
     fn testing_arithmetics() {
         let x = construct("{0.0 | }");
@@ -439,25 +1139,278 @@ mod tests {
         assert_eq!(add(&z, &z), zero());
         assert_eq!(add(&z, &x), x);
         assert_eq!(add(&y, &z), y);
+        // 2 + -3 in simplest form
         assert_eq!(
             add(&construct("{1.0 | }"), &construct("{ | -2.0}")),
-            construct("{ -2.0 | 0.0 }")
+            construct("{ | 0.0 }")
         );
 
         assert_eq!(
             pdt_add(
                 &construct("{1.0, 2.0, 3.0, 4.0, 5.0 | }"),
-                6.0,
+                Dyadic::integer(6),
                 &construct("{ | -2.0}"),
-                -3.0
+                Dyadic::integer(-3)
             ),
             construct("{-2.0, -1.0, 0.0, 1.0, 2.0 | 4.0}")
         );
 
         let half = construct("{0|1}"); // 0.5
         assert_eq!(
-            pdt_add(&half, 0.5, &half, 0.5),
+            pdt_add(&half, Dyadic::new(1, 1), &half, Dyadic::new(1, 1)),
             construct("{0.5, 0.5 | 1.5, 1.5}")
         );
     }
+
+    #[test]
+    fn testing_recursive_addition() {
+        let half = construct("{ 0 | 1 }");
+        assert_eq!(add(&half, &half), construct("{ 0 | }"));
+        // *2 + *3 = *1 and * + * = 0, as nim sums
+        let sum = add(&star(2), &star(3));
+        assert!(value_eq(&sum, &star(1)));
+        assert!(!value_eq(&sum, &star(2)) && !value_eq(&sum, &zero()));
+        assert!(value_eq(&add(&star(1), &star(1)), &zero()));
+        assert!(value_eq(&add(&star(3), &star(3)), &zero()));
+
+        // numbers plus games: 1/2 + * is confused with 1/2, up + up > up, 3 + * = { 3 | 3 }
+        let s1 = star(1);
+        let up = construct("{ 0 | { 0 | 0 } }");
+        let half_star = add(&half, &s1);
+        assert_eq!(game_cmp(&half_star, &half), GameOrdering::Fuzzy);
+        assert!(lt(&half_star, &construct("{ 1 | }")));
+        assert!(lt(&up, &add(&up, &up)));
+        assert!(value_eq(
+            &add(&construct("{ 2 | }"), &s1),
+            &construct("{ 3 | 3 }")
+        ));
+        assert!(value_eq(
+            &add(&s1, &construct("{ 2 | }")),
+            &construct("{ 3 | 3 }")
+        ));
+
+        // x - x = 0 for numbers in non-simplest forms and for nested games
+        for x in [
+            construct("{ -1, { -1 | 1 } | { 1 | 2 }, 1 }"),
+            construct("{ 0, { 0 | 0 } | 1/2 }"),
+            up,
+            star(3),
+            crate::create_complex_par_surreal(),
+        ] {
+            assert!(value_eq(&add(&x, &negate(&x)), &zero()), "{}", x);
+        }
+        // (1/2 + *) + (1/2 + *) = 1
+        assert!(value_eq(
+            &add(&half_star, &half_star),
+            &construct("{ 0 | }")
+        ));
+    }
+
+    #[test]
+    fn testing_canonical() {
+        // reversible options, through 0 <= { * | * } and 1 >= { -1, 0 | 1, 2 }
+        assert!(structural_eq(
+            &canonical(&construct("{ { 0 | 0 } | { 0 | 0 } }")),
+            &zero()
+        ));
+        assert!(structural_eq(
+            &canonical(&construct("{ -1, 0 | 1, 2 }")),
+            &construct("{ 0 | 1 }")
+        ));
+        // dominated options, and numbers in any form become leaves
+        assert!(structural_eq(
+            &canonical(&construct("{ -1, { -1 | 1 } | { 1 | 2 }, 1 }")),
+            &construct("{ 0 | 1 }")
+        ));
+        assert!(structural_eq(
+            &canonical(&construct("{ { | 7 }, 2 | }")),
+            &construct("{ 2 | }")
+        ));
+        assert!(structural_eq(
+            &canonical(&construct(
+                "{ { { 0 | 0 } | { 0 | 0 } }, 0, { 0 | 0 } | 0, { 0 | 0 }, { 0 | } }"
+            )),
+            &star(2)
+        ));
+        // already canonical: ±1, *3 and up
+        let hot = construct("{ 1 | -1 }");
+        assert!(structural_eq(&canonical(&hot), &hot));
+        assert!(structural_eq(&canonical(&star(3)), &star(3)));
+        let up = construct("{ 0 | { 0 | 0 } }");
+        assert!(structural_eq(&canonical(&up), &up));
+
+        // ↑ + ↑ = { ↑ | ↑* }, where ↑ reverses through * to leave ⇑ = { 0 | ↑* }
+        let double_up = canonical(&add(&up, &up));
+        assert!(structural_eq(
+            &double_up,
+            &construct("{ 0 | { 0, { 0 | 0 } | 0 } }")
+        ));
+        assert_eq!(double_up.to_string(), "{ 0 | { 0, { 0 | 0 } | 0 } }");
+        assert!(structural_eq(
+            &canonical(&add(&star(2), &star(3))),
+            &star(1)
+        ));
+
+        let complex = crate::create_complex_par_surreal();
+        let reduced = canonical(&complex);
+        assert!(value_eq(&reduced, &complex));
+        assert!(size(&reduced) < size(&complex));
+        assert!(structural_eq(&canonical(&reduced), &reduced));
+        assert!(structural_eq(
+            &canonical(&negate(&complex)),
+            &canonical(&negate(&reduced))
+        ));
+
+        // equal values hash alike
+        let set: HashSet<Surreal> = [
+            construct("{ 0 | }"),
+            construct("{ -1, 0 | }"),
+            construct("{ { { 0 | 0 } | { 0 | 0 } }, 0, { 0 | 0 } | 0, { 0 | 0 }, { 0 | } }"),
+            construct("{ 0, { 0 | 0 } | 0, { 0 | 0 } }"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn testing_multiplication() {
+        let half = construct("{ 0 | 1 }");
+        let other_half = construct("{ -1, { -1 | 1 } | { 1 | 2 }, 1 }");
+        assert_eq!(mul(&half, &half), construct("{ 0 | 1/2 }"));
+        assert_eq!(
+            mul(&other_half, &construct("{ 2 | }")),
+            construct("{ 1 | 2 }")
+        );
+        assert_eq!(
+            mul(&construct("{ | -2 }"), &construct("{ 1/4 | 1/2 }")),
+            from_dyadic(Dyadic::new(-9, 3))
+        );
+        assert_eq!(mul(&zero(), &half), zero());
+
+        // * 1 = * * = *, * 0 = 0 and -1 * = *
+        let s1 = star(1);
+        let one = construct("{ 0 | }");
+        assert!(value_eq(&mul(&s1, &one), &s1));
+        assert!(value_eq(&mul(&one, &s1), &s1));
+        assert!(value_eq(&mul(&s1, &s1), &s1));
+        assert!(value_eq(&mul(&s1, &zero()), &zero()));
+        assert!(value_eq(&mul(&construct("{ | 0 }"), &s1), &s1));
+        // 2 up = up + up
+        let up = construct("{ 0 | { 0 | 0 } }");
+        let two = construct("{ 1 | }");
+        assert!(value_eq(&mul(&two, &up), &add(&up, &up)));
+        assert!(value_eq(&mul(&up, &star(2)), &mul(&star(2), &up)));
+        // products and sums of games come back canonical: 2 up = { 0 | up* }
+        assert!(structural_eq(&mul(&s1, &one), &s1));
+        assert!(structural_eq(
+            &mul(&two, &up),
+            &construct("{ 0 | { 0, { 0 | 0 } | 0 } }")
+        ));
+        for p in [mul(&up, &star(2)), add(&up, &s1), add(&star(2), &star(3))] {
+            assert!(structural_eq(&canonical(&p), &p));
+        }
+
+        // distributivity over nested numeric forms, kept as leaves along the way
+        let x = construct("{ { 0 | 1 } | { 1 | } }");
+        let y = construct("{ -1 | { 0 | 1/2 } }");
+        let z = construct("{ 1/8 | 3/16 }");
+        let lhs = mul(&x, &add(&y, &z));
+        let rhs = add(&mul(&x, &y), &mul(&x, &z));
+        assert!(value_eq(&lhs, &rhs));
+        assert_eq!(value(&lhs), Some(Dyadic::new(5, 5)));
+    }
+
+    #[test]
+    fn testing_reciprocal() {
+        let exact = |form: &str, expected: Dyadic| {
+            let (y, precision) = reciprocal(&construct(form), 20).unwrap();
+            assert_eq!(precision, Precision::Exact, "1/{}", form);
+            assert_eq!(value(&y), Some(expected), "1/{}", form);
+        };
+        exact("{ 0 | }", Dyadic::integer(1));
+        exact("{ 1 | }", Dyadic::new(1, 1));
+        exact("{ 3 | }", Dyadic::new(1, 2));
+        exact("{ 0 | 1 }", Dyadic::integer(2));
+        exact("{ 0 | 1/4 }", Dyadic::integer(8));
+        exact("{ | -3 }", Dyadic::new(-1, 2));
+        exact("{ -1, 1/2 | { 1 | } }", Dyadic::integer(1));
+
+        // 1/3 = 1/4 + 1/16 + ... is reached through 1, 1/2, 1/4, 3/8, 5/16, 11/32, ...
+        let three = construct("{ 2 | }");
+        let days: Vec<Option<Dyadic>> = (1..=7)
+            .map(|day| {
+                let (y, precision) = reciprocal(&three, day).unwrap();
+                assert_eq!(precision, Precision::Truncated);
+                value(&y)
+            })
+            .collect();
+        let expected = [(1, 0), (1, 1), (1, 2), (3, 3), (5, 4), (11, 5), (21, 6)];
+        assert_eq!(
+            days,
+            expected
+                .iter()
+                .map(|&(n, e)| Some(Dyadic::new(n, e)))
+                .collect::<Vec<_>>()
+        );
+        // 1/(3/4) = 4/3 and 1/(-5)
+        let (y, precision) = reciprocal(&construct("{ 1/2 | 1 }"), 10).unwrap();
+        assert_eq!(precision, Precision::Truncated);
+        assert_eq!(value(&y).unwrap().birthday(), 10);
+        let gap = value(&y)
+            .unwrap()
+            .to_frac()
+            .unwrap()
+            .sub(&Frac { numer: 4, denom: 3 });
+        assert!(gap.numer.abs() * 256 < gap.denom);
+        let (y, _) = reciprocal(&construct("{ | -4 }"), 8).unwrap();
+        assert!(lt(&y, &zero()) && lt(&construct("{ | -1/4 }"), &y));
+
+        assert!(reciprocal(&zero(), 5).is_none());
+        assert!(reciprocal(&star(1), 5).is_none());
+
+        // 3/2 / 3 = 1/2 only through an approximation of 1/3, 1 / 4 exactly
+        let (q, precision) = div(&construct("{ 1 | 2 }"), &three, 12).unwrap();
+        assert_eq!(precision, Precision::Truncated);
+        let gap = value(&q).unwrap().sub(&Dyadic::new(1, 1));
+        assert!(gap.to_f64().abs() < 1e-3);
+        let (q, precision) = div(&construct("{ 1 | 2 }"), &construct("{ 3 | }"), 12).unwrap();
+        assert_eq!(precision, Precision::Exact);
+        assert_eq!(value(&q), Some(Dyadic::new(3, 3)));
+        assert_eq!(
+            div(&zero(), &three, 3).map(|(_, p)| p),
+            Some(Precision::Exact)
+        );
+    }
+
+    #[test]
+    fn testing_dyadic_leaves() {
+        let x = construct("{ 1/2, -0.75 | 3/8, { 1.5 | } }");
+        assert_eq!(
+            x.l,
+            Some(vec![
+                SurrealValue::Dyadic(Dyadic::new(1, 1)),
+                SurrealValue::Dyadic(Dyadic::new(-3, 2)),
+            ])
+        );
+        assert_eq!(format!("{}", x), "{ 1/2, -3/4 | 3/8, { 3/2 |  } }");
+        assert_eq!(construct("{ 0.5 | }"), construct("{ 2/4 | }"));
+        assert_eq!(construct("{ 2.0 | }"), construct("{ 2 | }"));
+
+        // 60 halvings stay exact where f64 leaves would have lost 2^-60 next to 1
+        let mut g = construct("{ 1 | 2 }");
+        for _ in 0..60 {
+            g = Surreal {
+                l: Some(vec![SurrealValue::Dyadic(Dyadic::integer(1))]),
+                r: Some(vec![SurrealValue::Surreal(g)]),
+            };
+        }
+        let v = value(&g).unwrap();
+        assert_eq!(v, Dyadic::new((1 << 61) + 1, 61));
+        assert!(lt(&construct("{ 0 | }"), &g) && lt(&g, &construct("{ 1 | 2 }")));
+        // 5/16 + -1/16
+        let sum = add(&construct("{ 1/4 | 3/8 }"), &construct("{ -1/8 | 0 }"));
+        assert_eq!(sum, construct("{ 0 | 1/2 }"));
+        assert_eq!(value(&sum), Some(Dyadic::new(1, 2)));
+    }
 }
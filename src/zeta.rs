@@ -0,0 +1,282 @@
+use crate::dispatch::*;
+use crate::frac::*;
+use crate::padic::*;
+
+fn gcd128(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+// (numer, denom) over i128, None on overflow
+fn add128(x: (i128, i128), y: (i128, i128)) -> Option<(i128, i128)> {
+    let n = x.0.checked_mul(y.1)?.checked_add(y.0.checked_mul(x.1)?)?;
+    let d = x.1.checked_mul(y.1)?;
+    let g = gcd128(n, d).max(1);
+    Some((n / g, d / g))
+}
+
+fn binomial(n: i64, k: i64) -> i128 {
+    (0..k).fold(1i128, |acc, i| acc * (n - i) as i128 / (i + 1) as i128)
+}
+
+// B_0, ..., B_n from sum_{i <= m} C(m + 1, i) B_i = 0, None once they outgrow i64
+fn bernoulli_numbers(n: usize) -> Option<Vec<Frac>> {
+    let mut b: Vec<(i128, i128)> = vec![(1, 1)];
+    for m in 1..=n {
+        let mut sum = (0i128, 1i128);
+        for (i, &(num, den)) in b.iter().enumerate() {
+            let c = binomial(m as i64 + 1, i as i64);
+            sum = add128(sum, (num.checked_mul(c)?, den))?;
+        }
+        let d = sum.1.checked_mul(m as i128 + 1)?;
+        let g = gcd128(sum.0, d).max(1);
+        b.push((-sum.0 / g, d / g));
+    }
+    b.iter()
+        .map(|&(num, den)| {
+            Some(Frac {
+                numer: num.try_into().ok()?,
+                denom: den.try_into().ok()?,
+            })
+        })
+        .collect()
+}
+
+// the Bernoulli number B_n with B_1 = -1/2, None when it does not fit a Frac (n > 34)
+#[allow(dead_code)]
+pub fn bernoulli(n: usize) -> Option<Frac> {
+    bernoulli_numbers(n).map(|b| b[n])
+}
+
+// B_0, ..., B_n mod p, valid while n < p - 1 so that no denominator is divisible by p; the
+// binomials C(m + 1, i) come from Pascal's rule mod p, as they outgrow i128 for p past 130
+fn bernoulli_mod_p(n: usize, p: i64) -> Vec<i64> {
+    let mut b = vec![1i64];
+    let mut row = vec![1i64, 1];
+    for m in 1..=n as i64 {
+        row = (0..row.len() + 1)
+            .map(|i| {
+                let below = if i > 0 { row[i - 1] } else { 0 };
+                (row.get(i).unwrap_or(&0) + below) % p
+            })
+            .collect();
+        let sum = b.iter().zip(&row).map(|(&bi, &c)| c * bi % p).sum::<i64>() % p;
+        b.push((p - sum) % p * mod_inverse(m + 1, p).unwrap() % p);
+    }
+    b
+}
+
+// the even k in 2..=p-3 with p dividing the numerator of B_k
+#[allow(dead_code)]
+pub fn irregular_indices(p: i64) -> Vec<i64> {
+    if p < 5 {
+        return vec![];
+    }
+    let b = bernoulli_mod_p(p as usize - 3, p);
+    (2..=p - 3)
+        .step_by(2)
+        .filter(|&k| b[k as usize] == 0)
+        .collect()
+}
+
+#[allow(dead_code)]
+pub fn is_irregular(p: i64) -> bool {
+    !irregular_indices(p).is_empty()
+}
+
+#[allow(dead_code)]
+pub fn irregular_primes(limit: i64) -> Vec<i64> {
+    // the Bernoulli numbers up to p take about p^2 steps
+    let work = (limit.max(0) as usize).pow(2);
+    map_range(3..limit + 1, work, |n| {
+        let prime = (2..).take_while(|d| d * d <= n).all(|d| n % d != 0);
+        (prime && is_irregular(n)).then_some(n)
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+fn int(n: i64, p: i64, precision: usize) -> Padic {
+    expand(&Frac { numer: n, denom: 1 }, p, precision)
+}
+
+// ω(a), the root of unity congruent to a mod p, or to a mod 4 when p = 2
+fn omega(a: i64, p: i64, precision: usize) -> Padic {
+    if p == 2 {
+        int(if a % 4 == 1 { 1 } else { -1 }, p, precision)
+    } else {
+        int(a, p, precision).teichmuller()
+    }
+}
+
+// Kubota-Leopoldt L_p(1 - k, ω^j) for 1 <= k <= 34, i.e. -1/k times the Volkenborn integral
+// of ω^j(a)<a>^k over Z_p^*, evaluated in closed form through the generalized Bernoulli
+// number B_{k,ψ} with ψ = ω^(j - k):
+//   L_p(1 - k, ω^j) = -(1 - ψ(p) p^(k - 1)) B_{k,ψ} / k,  B_{k,ψ} = f^(k-1) sum ψ(a) B_k(a / f)
+#[allow(dead_code)]
+pub fn padic_zeta(k: i64, j: i64, p: i64, precision: usize) -> Option<Padic> {
+    if k < 1 {
+        return None;
+    }
+    let bernoulli = bernoulli_numbers(k as usize)?;
+    let work = precision + k as usize + 4;
+    let order = if p == 2 { 2 } else { p - 1 };
+
+    let integral = if (j - k).rem_euclid(order) == 0 {
+        // ψ is trivial: (1 - p^(k - 1)) B_k
+        let euler = int(1, p, work).sub(&int(p, p, work).pow(k - 1));
+        euler.mul(&expand(&bernoulli[k as usize], p, work))
+    } else {
+        // f^(k - 1) B_k(a / f) = sum_i C(k, i) B_i a^(k - i) f^(i - 1)
+        let f = if p == 2 { 4 } else { p };
+        let f_padic = int(f, p, work);
+        let e = (j - k).rem_euclid(order);
+        // k + 1 terms of products quadratic in the digits for each a
+        let cost = f as usize * (k as usize + 1) * work.pow(2);
+        map_range(1..f, cost, |a| {
+            if a % p == 0 {
+                return None;
+            }
+            let a_padic = int(a, p, work);
+            let poly = (0..=k).fold(int(0, p, work), |acc, i| {
+                let term = expand(&bernoulli[i as usize], p, work)
+                    .mul(&int(binomial(k, i) as i64, p, work))
+                    .mul(&a_padic.pow(k - i))
+                    .mul(&f_padic.pow(i - 1));
+                acc.add(&term)
+            });
+            Some(omega(a, p, work).pow(e).mul(&poly))
+        })
+        .into_iter()
+        .flatten()
+        .fold(int(0, p, work), |x, y| x.add(&y))
+    };
+    let value = integral.neg().div(&int(k, p, work))?.normalized();
+    let digits = precision.min(value.expanded.len());
+    Some(Padic::with_digits(
+        p,
+        value.expanded[..digits].to_vec(),
+        value.offset,
+        None,
+    ))
+}
+
+// Kummer's congruence (1 - p^(k1 - 1)) B_k1 / k1 = (1 - p^(k2 - 1)) B_k2 / k2 mod p^e for
+// k1 = k2 mod (p - 1)p^(e - 1) not divisible by p - 1 and e >= 1, None when the hypotheses
+// fail
+#[allow(dead_code)]
+pub fn kummer_congruence(k1: i64, k2: i64, p: i64, e: usize) -> Option<bool> {
+    if e == 0 {
+        return None;
+    }
+    let period = (p - 1) * p.pow(e as u32 - 1);
+    if k1 % (p - 1) == 0 || k2 % (p - 1) == 0 || (k1 - k2) % period != 0 {
+        return None;
+    }
+    let (x, y) = join(
+        (k1 + k2) as usize * p as usize * (e + 2).pow(2),
+        || padic_zeta(k1, k1, p, e + 2),
+        || padic_zeta(k2, k2, p, e + 2),
+    );
+    let difference = x?.sub(&y?);
+    Some(difference.valuation().is_none_or(|v| v >= e as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn testing_bernoulli() {
+        assert_eq!(bernoulli(0), Some(Frac { numer: 1, denom: 1 }));
+        assert_eq!(
+            bernoulli(1),
+            Some(Frac {
+                numer: -1,
+                denom: 2
+            })
+        );
+        assert_eq!(bernoulli(3), Some(Frac { numer: 0, denom: 1 }));
+        assert_eq!(
+            bernoulli(12),
+            Some(Frac {
+                numer: -691,
+                denom: 2730
+            })
+        );
+        assert_eq!(
+            bernoulli(30),
+            Some(Frac {
+                numer: 8615841276005,
+                denom: 14322
+            })
+        );
+        assert_eq!(bernoulli(40), None);
+    }
+
+    #[test]
+    fn testing_irregular_primes() {
+        assert_eq!(irregular_indices(37), vec![32]);
+        assert!(!is_irregular(31));
+        assert_eq!(irregular_primes(110), vec![37, 59, 67, 101, 103]);
+        // past 130, where C(p - 2, i) no longer fits an i128
+        assert_eq!(irregular_indices(131), vec![22]);
+        assert_eq!(irregular_indices(157), vec![62, 110]);
+        assert_eq!(
+            irregular_primes(200),
+            vec![37, 59, 67, 101, 103, 131, 149, 157]
+        );
+    }
+
+    #[test]
+    fn testing_padic_zeta() {
+        // trivial ψ: -(1 - 5^3) B_4 / 4 = -31/30
+        let value = padic_zeta(4, 0, 5, 8).unwrap();
+        let expected = expand(
+            &Frac {
+                numer: -31,
+                denom: 30,
+            },
+            5,
+            8,
+        )
+        .normalized();
+        assert_eq!(value.offset, expected.offset);
+        assert_eq!(value.expanded, expected.expanded);
+
+        // odd characters vanish
+        assert_eq!(padic_zeta(2, 1, 5, 6).unwrap().valuation(), None);
+
+        // L_p(s, ω^2) is continuous in s: 1 - 1 and 1 - 6 agree mod 5
+        let x = padic_zeta(1, 2, 5, 6).unwrap();
+        let y = padic_zeta(6, 2, 5, 6).unwrap();
+        assert_eq!(
+            y.expanded,
+            expand(
+                &Frac {
+                    numer: 781,
+                    denom: 63
+                },
+                5,
+                6
+            )
+            .expanded
+        );
+        assert!(x.sub(&y).valuation().is_none_or(|v| v >= 1));
+
+        // p = 37 divides B_32, so L_37(1 - 32, ω^32) vanishes mod 37
+        assert!(padic_zeta(32, 32, 37, 3).unwrap().valuation().unwrap() >= 1);
+    }
+
+    #[test]
+    fn testing_kummer_congruences() {
+        assert_eq!(kummer_congruence(2, 6, 5, 1), Some(true));
+        assert_eq!(kummer_congruence(2, 22, 5, 2), Some(true));
+        assert_eq!(kummer_congruence(4, 8, 5, 1), None);
+        assert_eq!(kummer_congruence(2, 4, 5, 1), None);
+        assert_eq!(kummer_congruence(2, 6, 5, 0), None);
+    }
+}